@@ -0,0 +1,63 @@
+//! Reports the number of allocations made while parsing each data file, comparing the owned AST
+//! against the borrowed one from `parse_libs_ref`
+//!
+//! Both only build verbose errors after a failure, so the counts differ only by the AST.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use liberty_parse::{ast::LibertyAst, parse_libs_ref};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    let count = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    drop(result);
+    count
+}
+
+macro_rules! report_file {
+    ($fname:ident) => {{
+        let data = include_str!(concat!("../data/", stringify!($fname), ".lib"));
+        let owned = count_allocations(|| LibertyAst::from_string(data).unwrap());
+        let borrowed = count_allocations(|| parse_libs_ref(data).unwrap());
+        println!(
+            "{:<14} owned: {:>6} allocations, borrowed: {:>6} allocations",
+            stringify!($fname),
+            owned,
+            borrowed
+        );
+    }};
+}
+
+fn main() {
+    report_file!(small);
+    report_file!(cells);
+    report_file!(cells_timing);
+}
@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate criterion;
+
+use liberty_parse::{parse_lib, parse_lib_parallel};
+
+use criterion::Criterion;
+
+fn concatenated() -> String {
+    [
+        include_str!("../data/small.lib"),
+        include_str!("../data/cells.lib"),
+        include_str!("../data/cells_timing.lib"),
+    ]
+    .join("\n")
+}
+
+fn sequential(c: &mut Criterion) {
+    let data = concatenated();
+    c.bench_function("sequential", move |b| b.iter(|| parse_lib(&data).unwrap()));
+}
+
+fn parallel(c: &mut Criterion) {
+    let data = concatenated();
+    c.bench_function("parallel", move |b| {
+        b.iter(|| parse_lib_parallel(&data).unwrap())
+    });
+}
+
+criterion_group!(benches, sequential, parallel);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+#[macro_use]
+extern crate criterion;
+
+use liberty_parse::liberty::Group;
+use liberty_parse::table::LookupTable;
+
+use criterion::Criterion;
+
+const READS: usize = 100;
+
+fn table_group() -> Group {
+    LookupTable {
+        index_1: (0..7).map(f64::from).collect(),
+        index_2: (0..7).map(f64::from).collect(),
+        values: (0..7)
+            .map(|i| (0..7).map(|j| f64::from(i * j)).collect())
+            .collect(),
+    }
+    .to_group("cell_rise")
+}
+
+fn uncached(c: &mut Criterion) {
+    let group = table_group();
+    c.bench_function("uncached", move |b| {
+        b.iter(|| {
+            (0..READS)
+                .map(|i| group.as_lookup_table().unwrap().values[i % 7][i % 5])
+                .sum::<f64>()
+        })
+    });
+}
+
+fn cached(c: &mut Criterion) {
+    let group = table_group();
+    c.bench_function("cached", move |b| {
+        b.iter(|| {
+            let cached = group.cached_lookup_table();
+            (0..READS)
+                .map(|i| cached.get().unwrap().values[i % 7][i % 5])
+                .sum::<f64>()
+        })
+    });
+}
+
+criterion_group!(benches, uncached, cached);
+criterion_main!(benches);
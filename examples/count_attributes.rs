@@ -0,0 +1,37 @@
+use liberty_parse::{
+    liberty::{Group, ToGroup},
+    parse_lib,
+    visit::GroupVisitor,
+    Value,
+};
+use std::{collections::BTreeMap, env, fs};
+
+/// Counts how many times each attribute name appears
+#[derive(Default)]
+struct AttributeCounter(BTreeMap<String, usize>);
+
+impl GroupVisitor for AttributeCounter {
+    fn simple_attr(&mut self, name: &str, _value: &Value, _depth: usize) {
+        *self.0.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    fn complex_attr(&mut self, name: &str, _values: &[Value], _depth: usize) {
+        *self.0.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        panic!("Missing LIB file argument");
+    }
+    let contents = fs::read_to_string(&args[1]).expect("Unable to read LIB file");
+    let mut counter = AttributeCounter::default();
+    for lib in parse_lib(&contents).unwrap() {
+        let group: Group = lib.into_group();
+        group.visit(&mut counter);
+    }
+    for (name, count) in counter.0 {
+        println!("{}: {}", name, count);
+    }
+}
@@ -0,0 +1,196 @@
+//! Flat representation of a [`Liberty`] structure with links to parent groups
+//!
+//! [`Group`] owns its sub-groups, so there's no way to get from a `pin` back to its `cell`.
+//! [`LibertyArena`] stores every group in one `Vec` and links them by index instead, so it can
+//! be walked in both directions.
+//!
+//! ```
+//! use liberty_parse::{arena::LibertyArena, parse_lib};
+//!
+//! let liberty = parse_lib("library(foo) { cell(AND2) { pin(A) { } } }").unwrap();
+//! let arena = LibertyArena::from_liberty(liberty);
+//! let lib = arena.roots()[0];
+//! let cell = arena.children(lib)[0];
+//! let pin = arena.children(cell)[0];
+//! assert_eq!(arena.name(pin), "A");
+//! assert_eq!(arena.parent(pin), Some(cell));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::ast::Value;
+use crate::liberty::{AttributeOrder, FromGroup, Group, Liberty, Library, ToGroup};
+
+/// Group stored in a [`LibertyArena`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArenaGroup {
+    pub type_: String,
+    pub name: String,
+    pub simple_attributes: HashMap<String, Value>,
+    pub complex_attributes: HashMap<String, Vec<Value>>,
+    pub comments: HashMap<String, String>,
+    pub attribute_order: AttributeOrder,
+    pub unknown: Vec<String>,
+    /// Index of the enclosing group, or `None` for a library
+    pub parent: Option<usize>,
+    /// Indices of the sub-groups, in order
+    pub children: Vec<usize>,
+}
+
+/// Every group of a [`Liberty`] structure, indexed by position
+///
+/// Groups are numbered in depth-first order, so a group's index is always greater than its
+/// parent's. Cells and pins are in name order, as in [`ToGroup::into_group`]. Methods taking an
+/// index panic if it's out of range.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LibertyArena {
+    groups: Vec<ArenaGroup>,
+    roots: Vec<usize>,
+}
+
+impl LibertyArena {
+    /// Flatten every library of `liberty` into an arena
+    pub fn from_liberty(liberty: Liberty) -> Self {
+        let mut arena = LibertyArena::default();
+        for lib in liberty {
+            let root = arena.push(lib.into_group(), None);
+            arena.roots.push(root);
+        }
+        arena
+    }
+
+    /// Rebuild the [`Liberty`] structure
+    pub fn into_liberty(mut self) -> Liberty {
+        let roots = std::mem::take(&mut self.roots);
+        Liberty(
+            roots
+                .into_iter()
+                .map(|root| Library::from_group(self.take(root)))
+                .collect(),
+        )
+    }
+
+    /// Indices of the libraries
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Number of groups at any depth
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether the arena has no groups
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Get a group by index
+    pub fn get(&self, id: usize) -> &ArenaGroup {
+        &self.groups[id]
+    }
+
+    /// Get a mutable reference to a group by index
+    ///
+    /// Changing `parent` or `children` isn't checked, so callers must keep the links consistent.
+    pub fn get_mut(&mut self, id: usize) -> &mut ArenaGroup {
+        &mut self.groups[id]
+    }
+
+    /// Index of the group enclosing `id`, or `None` for a library
+    pub fn parent(&self, id: usize) -> Option<usize> {
+        self.groups[id].parent
+    }
+
+    /// Indices of the sub-groups of `id`
+    pub fn children(&self, id: usize) -> &[usize] {
+        &self.groups[id].children
+    }
+
+    /// Name of the group `id`
+    pub fn name(&self, id: usize) -> &str {
+        &self.groups[id].name
+    }
+
+    /// Type of the group `id`, such as `cell`
+    pub fn type_(&self, id: usize) -> &str {
+        &self.groups[id].type_
+    }
+
+    /// Iterate over the enclosing groups of `id`, nearest first
+    pub fn ancestors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent(id), move |&id| self.parent(id))
+    }
+
+    fn push(&mut self, group: Group, parent: Option<usize>) -> usize {
+        let id = self.groups.len();
+        self.groups.push(ArenaGroup {
+            type_: group.type_,
+            name: group.name,
+            simple_attributes: group.simple_attributes,
+            complex_attributes: group.complex_attributes,
+            comments: group.comments,
+            attribute_order: group.attribute_order,
+            unknown: group.unknown,
+            parent,
+            children: vec![],
+        });
+        for subgroup in group.groups {
+            let child = self.push(subgroup, Some(id));
+            self.groups[id].children.push(child);
+        }
+        id
+    }
+
+    // Move the group `id` and its sub-groups out of the arena
+    fn take(&mut self, id: usize) -> Group {
+        let node = &mut self.groups[id];
+        let children = std::mem::take(&mut node.children);
+        let mut group = Group {
+            type_: std::mem::take(&mut node.type_),
+            name: std::mem::take(&mut node.name),
+            simple_attributes: std::mem::take(&mut node.simple_attributes),
+            complex_attributes: std::mem::take(&mut node.complex_attributes),
+            comments: std::mem::take(&mut node.comments),
+            attribute_order: std::mem::take(&mut node.attribute_order),
+            unknown: std::mem::take(&mut node.unknown),
+            groups: vec![],
+        };
+        group.groups = children.into_iter().map(|child| self.take(child)).collect();
+        group
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arena() {
+        let liberty = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        let arena = LibertyArena::from_liberty(liberty.clone());
+        let lib = arena.roots()[0];
+        let and2 = arena
+            .children(lib)
+            .iter()
+            .copied()
+            .find(|&id| arena.name(id) == "AND2")
+            .unwrap();
+        let pin = arena
+            .children(and2)
+            .iter()
+            .copied()
+            .find(|&id| arena.type_(id) == "pin" && arena.name(id) == "a")
+            .unwrap();
+        let cell = arena.parent(pin).unwrap();
+        assert_eq!(cell, and2);
+        assert_eq!(arena.type_(cell), "cell");
+        assert_eq!(arena.parent(cell), Some(lib));
+        assert_eq!(arena.parent(lib), None);
+        assert_eq!(arena.ancestors(pin).collect::<Vec<_>>(), vec![cell, lib]);
+        assert!(arena.children(cell).contains(&pin));
+        assert_eq!(arena.get(lib).type_, "library");
+
+        assert_eq!(arena.into_liberty(), liberty);
+    }
+}
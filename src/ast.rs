@@ -2,14 +2,21 @@
 //! file.
 //!
 
-use std::result;
+use std::{
+    borrow::Cow,
+    convert::TryFrom,
+    error, fmt,
+    hash::{Hash, Hasher},
+    result,
+    str::FromStr,
+};
 
-use crate::error::Error;
+use crate::error::{Error, OwnedError};
 use crate::liberty::Liberty;
-use crate::parser::parse_libs;
+use crate::parser::{parse_libs, ParseConfig};
 
 use itertools::Itertools;
-use nom::error::VerboseError;
+use nom::error::{ErrorKind, VerboseError};
 
 /// Result type for parsing
 pub type ParseResult<'a, T> = result::Result<T, Error<'a>>;
@@ -28,15 +35,22 @@ impl LibertyAst {
     }
 
     /// Parse a Liberty file's string representation into the AST
-    pub fn from_string(input: &str) -> ParseResult<Self> {
-        parse_libs::<VerboseError<&str>>(input)
-            .map_err(|e| Error::new(input, e))
-            .map(|(_, libs)| LibertyAst::new(libs))
+    pub fn from_string(input: &str) -> ParseResult<'_, Self> {
+        Self::from_string_with_config(input, &ParseConfig::default())
     }
 
-    /// Get a `String` representation from an AST
-    pub fn to_string(&self) -> String {
-        items_to_string(&self.0)
+    // Parse with the syntax relaxed as set by `config`
+    pub(crate) fn from_string_with_config<'a>(
+        input: &'a str,
+        config: &ParseConfig,
+    ) -> ParseResult<'a, Self> {
+        // Verbose errors allocate on every backtrack, so only use them to describe a failure
+        match parse_libs::<(&str, ErrorKind)>(config, input) {
+            Ok((_, libs)) => Ok(LibertyAst::new(libs)),
+            Err(_) => parse_libs::<VerboseError<&str>>(config, input)
+                .map_err(|e| Error::new(input, e))
+                .map(|(_, libs)| LibertyAst::new(libs)),
+        }
     }
 
     /// Convert an AST into a [`Liberty`] struct
@@ -50,26 +64,246 @@ impl LibertyAst {
     }
 }
 
-// Recursively convert a vector of [`GroupItem`]s into a single `String`
-fn items_to_string(items: &Vec<GroupItem>) -> String {
+impl FromStr for LibertyAst {
+    type Err = OwnedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(LibertyAst::from_string(s)?)
+    }
+}
+
+impl fmt::Display for LibertyAst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_items(f, &self.0, &FormatOptions::default())
+    }
+}
+
+/// Options for [`LibertyAst::format`]
+///
+/// The default options produce the same output as [`LibertyAst`]'s `Display` implementation.
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Pad simple attribute names to the longest name in their group so the colons line up
+    ///
+    /// Complex attributes have no colon and aren't padded.
+    pub align_colons: bool,
+    /// Layout of the output
+    pub style: FormatStyle,
+    /// Newline used throughout the output, including inside comments
+    pub line_ending: LineEnding,
+    /// Spacing around the parentheses of group names and complex attribute values
+    pub parens: ParenStyle,
+}
+
+/// Parenthesis spacing used by [`FormatOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParenStyle {
+    /// `cell(AND2) {` and `pin_names(a, b);`, as written by most tools
+    #[default]
+    Tight,
+    /// `cell ( AND2 ) {`, with complex attribute values on their own line such as
+    /// `pin_names (\na, b\n);`
+    Spaced,
+}
+
+/// Newline used by [`FormatOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`, for tools that expect Windows line endings
+    CrLf,
+}
+
+/// Output layout used by [`FormatOptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatStyle {
+    /// Each attribute and group on its own lines
+    #[default]
+    Expanded,
+    /// Each top-level group on a single line, for grepping and log messages
+    ///
+    /// The output is still valid Liberty, but line breaks in comments and unrecognized
+    /// statements are replaced with spaces. `align_colons` has no effect.
+    Compact,
+}
+
+impl LibertyAst {
+    /// Convert the AST into a Liberty file's string representation
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let output = match options.style {
+            FormatStyle::Expanded => {
+                let mut output = String::new();
+                write_items(&mut output, &self.0, options).expect("writing to a String can't fail");
+                output
+            }
+            FormatStyle::Compact => items_to_compact_string(&self.0, options.parens),
+        };
+        match options.line_ending {
+            LineEnding::Lf => output,
+            LineEnding::CrLf => output.replace("\r\n", "\n").replace('\n', "\r\n"),
+        }
+    }
+}
+
+// Recursively convert a vector of [`GroupItem`]s into a single line
+fn items_to_compact_string(items: &[GroupItem], parens: ParenStyle) -> String {
     items
         .iter()
-        .map(|item| match item {
-            GroupItem::SimpleAttr(name, value) => format!("{} : {};\n", name, value.to_string()),
-            GroupItem::ComplexAttr(name, values) => format!(
-                "{} (\n{}\n)\n",
+        .map(|item| match (item, parens) {
+            (GroupItem::SimpleAttr(name, value), _) => format!("{} : {};", name, value),
+            (GroupItem::ComplexAttr(name, values), ParenStyle::Tight) => {
+                format!("{}({});", name, values.iter().join(", "))
+            }
+            (GroupItem::ComplexAttr(name, values), ParenStyle::Spaced) => {
+                format!("{} ({});", name, values.iter().join(", "))
+            }
+            (GroupItem::Comment(v), _)
+            | (GroupItem::TrailingComment(v), _)
+            | (GroupItem::Unknown(v), _) => v.lines().map(str::trim).join(" "),
+            (GroupItem::Group(type_, name, group_items), ParenStyle::Tight) => format!(
+                "{}({}) {{ {} }}",
+                type_,
                 name,
-                values.iter().map(|v| v.to_string()).join(", ")
+                items_to_compact_string(group_items, parens)
             ),
-            GroupItem::Comment(v) => format!("/*\n{}\n*/", v),
-            GroupItem::Group(type_, name, group_items) => format!(
-                "{} ( {} ) {{\n{}\n}}",
+            (GroupItem::Group(type_, name, group_items), ParenStyle::Spaced) => format!(
+                "{} ( {} ) {{ {} }}",
                 type_,
                 name,
-                items_to_string(group_items)
+                items_to_compact_string(group_items, parens)
             ),
         })
-        .join("\n")
+        .join(" ")
+}
+
+// Width to pad simple attribute names to, so their colons line up
+fn colon_width<'a>(names: impl Iterator<Item = &'a str>, options: &FormatOptions) -> usize {
+    if options.align_colons {
+        names.map(str::len).max().unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+pub(crate) fn write_simple_attr<W: fmt::Write>(
+    w: &mut W,
+    name: &str,
+    value: &Value,
+    width: usize,
+    comment: Option<&str>,
+) -> fmt::Result {
+    write!(w, "{:width$} : {};", name, value, width = width)?;
+    write_line_end(w, comment)
+}
+
+pub(crate) fn write_complex_attr<W: fmt::Write>(
+    w: &mut W,
+    name: &str,
+    values: &[Value],
+    comment: Option<&str>,
+    parens: ParenStyle,
+) -> fmt::Result {
+    match parens {
+        ParenStyle::Tight => write!(w, "{}({});", name, values.iter().format(", "))?,
+        ParenStyle::Spaced => write!(w, "{} (\n{}\n);", name, values.iter().format(", "))?,
+    }
+    write_line_end(w, comment)
+}
+
+// End an attribute's line, keeping its trailing comment on the same line
+fn write_line_end<W: fmt::Write>(w: &mut W, comment: Option<&str>) -> fmt::Result {
+    match comment {
+        Some(comment) => writeln!(w, " {}", comment),
+        None => writeln!(w),
+    }
+}
+
+pub(crate) fn write_group_open<W: fmt::Write>(
+    w: &mut W,
+    type_: &str,
+    name: &str,
+    parens: ParenStyle,
+) -> fmt::Result {
+    match parens {
+        ParenStyle::Tight => writeln!(w, "{}({}) {{", type_, name),
+        ParenStyle::Spaced => writeln!(w, "{} ( {} ) {{", type_, name),
+    }
+}
+
+pub(crate) fn write_group_close<W: fmt::Write>(w: &mut W) -> fmt::Result {
+    write!(w, "\n}}")
+}
+
+// Write a slice of [`GroupItem`]s, separated by newlines
+//
+// Nested groups are kept on an explicit stack rather than written recursively, so trees of any
+// depth can be written
+fn write_items<W: fmt::Write>(
+    w: &mut W,
+    items: &[GroupItem],
+    options: &FormatOptions,
+) -> fmt::Result {
+    // Items of each open group, with their colon width and the index of the next item
+    let mut stack = vec![(items, group_colon_width(items, options), 0)];
+    while let Some((items, width, next)) = stack.last_mut() {
+        let (items, width, idx) = (*items, *width, *next);
+        let item = match items.get(idx) {
+            Some(item) => item,
+            None => {
+                stack.pop();
+                if !stack.is_empty() {
+                    write_group_close(w)?;
+                }
+                continue;
+            }
+        };
+        *next += 1;
+        // Trailing comments are written on the line of the attribute before them
+        if matches!(item, GroupItem::TrailingComment(_)) && idx > 0 && is_attr(&items[idx - 1]) {
+            continue;
+        }
+        if idx > 0 {
+            writeln!(w)?;
+        }
+        let comment = match items.get(idx + 1) {
+            Some(GroupItem::TrailingComment(comment)) => Some(comment.as_str()),
+            _ => None,
+        };
+        match item {
+            GroupItem::SimpleAttr(name, value) => {
+                write_simple_attr(w, name, value, width, comment)?
+            }
+            GroupItem::ComplexAttr(name, values) => {
+                write_complex_attr(w, name, values, comment, options.parens)?
+            }
+            GroupItem::Comment(v) | GroupItem::TrailingComment(v) => write!(w, "{}", v)?,
+            GroupItem::Unknown(v) => writeln!(w, "{}", v)?,
+            GroupItem::Group(type_, name, group_items) => {
+                write_group_open(w, type_, name, options.parens)?;
+                stack.push((group_items, group_colon_width(group_items, options), 0));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn group_colon_width(items: &[GroupItem], options: &FormatOptions) -> usize {
+    colon_width(
+        items.iter().filter_map(|item| match item {
+            GroupItem::SimpleAttr(name, _) => Some(name.as_str()),
+            _ => None,
+        }),
+        options,
+    )
+}
+
+fn is_attr(item: &GroupItem) -> bool {
+    matches!(
+        item,
+        GroupItem::SimpleAttr(_, _) | GroupItem::ComplexAttr(_, _)
+    )
 }
 
 /// Intermediate representation
@@ -80,8 +314,14 @@ pub enum GroupItem {
     // name, value
     SimpleAttr(String, Value),
     ComplexAttr(String, Vec<Value>),
-    // contents
+    // contents, including the `/*` and `*/`
     Comment(String),
+    // comment on the same line as the end of the attribute before it, such as `/* x */` in
+    // `area : 1; /* x */`. Kept with the attribute when converting to a `Liberty`
+    TrailingComment(String),
+    // raw text of an unrecognized statement, including its `;` or closing `}`. Preserved on
+    // output, and kept in the group's `unknown` when converting to a `Liberty`
+    Unknown(String),
 }
 
 impl GroupItem {
@@ -93,6 +333,92 @@ impl GroupItem {
             panic!("Not variant GroupItem::Group");
         }
     }
+
+    /// Insert `item` into a [`GroupItem::Group`] right after the attribute named `anchor`
+    ///
+    /// Returns `false`, leaving the group unchanged, if this isn't a group or it has no simple or
+    /// complex attribute named `anchor`. See
+    /// [`Group::insert_attribute_after`](crate::liberty::Group::insert_attribute_after) for the
+    /// same on a `Group`.
+    pub fn insert_attribute_after(&mut self, anchor: &str, item: GroupItem) -> bool {
+        if let GroupItem::Group(_, _, items) = self {
+            let position = items.iter().position(|i| match i {
+                GroupItem::SimpleAttr(name, _) | GroupItem::ComplexAttr(name, _) => name == anchor,
+                _ => false,
+            });
+            if let Some(idx) = position {
+                items.insert(idx + 1, item);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Borrowed counterpart of [`GroupItem`], returned by [`parse_libs_ref`](crate::parse_libs_ref)
+///
+/// Names, expressions, and comments borrow from the input. Group names and strings are only
+/// allocated when they differ from the input, such as a name joined from several group
+/// arguments or a string with line continuations.
+#[derive(Debug, PartialEq, Clone)]
+pub enum GroupItemRef<'a> {
+    // type, name, values
+    Group(&'a str, Cow<'a, str>, Vec<GroupItemRef<'a>>),
+    // name, value
+    SimpleAttr(&'a str, ValueRef<'a>),
+    ComplexAttr(&'a str, Vec<ValueRef<'a>>),
+    // contents, including the `/*` and `*/`
+    Comment(&'a str),
+    // comment on the same line as the end of the attribute before it
+    TrailingComment(&'a str),
+    // raw text of an unrecognized statement
+    Unknown(&'a str),
+}
+
+impl<'a> GroupItemRef<'a> {
+    /// Convert into an owned [`GroupItem`]
+    pub fn into_owned(self) -> GroupItem {
+        match self {
+            GroupItemRef::Group(type_, name, items) => GroupItem::Group(
+                type_.to_string(),
+                name.into_owned(),
+                items.into_iter().map(GroupItemRef::into_owned).collect(),
+            ),
+            GroupItemRef::SimpleAttr(name, value) => {
+                GroupItem::SimpleAttr(name.to_string(), value.into_owned())
+            }
+            GroupItemRef::ComplexAttr(name, values) => GroupItem::ComplexAttr(
+                name.to_string(),
+                values.into_iter().map(ValueRef::into_owned).collect(),
+            ),
+            GroupItemRef::Comment(v) => GroupItem::Comment(v.to_string()),
+            GroupItemRef::TrailingComment(v) => GroupItem::TrailingComment(v.to_string()),
+            GroupItemRef::Unknown(v) => GroupItem::Unknown(v.to_string()),
+        }
+    }
+}
+
+/// Borrowed counterpart of [`Value`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum ValueRef<'a> {
+    Bool(bool),
+    Float(f64),
+    FloatGroup(Vec<f64>),
+    String(Cow<'a, str>),
+    Expression(&'a str),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Convert into an owned [`Value`]
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Bool(v) => Value::Bool(v),
+            ValueRef::Float(v) => Value::Float(v),
+            ValueRef::FloatGroup(v) => Value::FloatGroup(v),
+            ValueRef::String(v) => Value::String(v.into_owned()),
+            ValueRef::Expression(v) => Value::Expression(v.to_string()),
+        }
+    }
 }
 
 /// Liberty value type
@@ -108,6 +434,8 @@ pub enum Value {
     ///
     /// All numbers are parsed into `f64`. While the Liberty specification differentiates between
     /// integers and floating point values on a per-field basis, all are parsed into an `f64`.
+    /// Hexadecimal (`0xFF`) and binary (`0b1010`) integers are parsed into this variant too, so
+    /// their radix isn't preserved on output.
     Float(f64),
     /// Group of floating point values in quotation marks
     ///
@@ -131,28 +459,212 @@ pub enum Value {
     Expression(String),
 }
 
+/// Infinite and NaN floats have no Liberty syntax, so they're written as the sentinels `1e999`,
+/// `-1e999`, and `nan`, which don't parse back as finite floats. Use
+/// [`Liberty::validate`](crate::liberty::Liberty::validate) to find them before writing.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(v) => write!(f, "\"{}\"", v),
+            Value::Expression(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", LibertyFloat(*v)),
+            Value::FloatGroup(v) => {
+                write!(f, "\"{}\"", v.iter().map(|v| LibertyFloat(*v)).format(", "))
+            }
+        }
+    }
+}
+
+// Float with 6 decimal places, or a sentinel if it isn't finite
+struct LibertyFloat(f64);
+
+impl fmt::Display for LibertyFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            v if v.is_nan() => write!(f, "nan"),
+            v if v == f64::INFINITY => write!(f, "1e999"),
+            v if v == f64::NEG_INFINITY => write!(f, "-1e999"),
+            v => write!(f, "{:.6}", v),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<i64> for Value {
+    /// Liberty has no integer type, so integers become a [`Value::Float`]
+    fn from(v: i64) -> Self {
+        Value::Float(v as f64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<&str> for Value {
+    /// Text becomes a quoted [`Value::String`]; use [`Value::expression`] for bare text
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    /// Text becomes a quoted [`Value::String`]; use [`Value::expression`] for bare text
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+/// Error converting a [`Value`] into a Rust type of a different variant
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueTypeError {
+    /// Variant, or variants, that the conversion accepts, such as `Float`, along with any limit
+    /// on the value
+    pub expected: &'static str,
+    /// Variant of the value that was converted
+    pub found: &'static str,
+}
+
+impl fmt::Display for ValueTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl error::Error for ValueTypeError {}
+
 impl Value {
-    /// Get a `String` representation of a [`Value`]
-    pub fn to_string(&self) -> String {
+    // Name of the variant, for errors
+    fn variant_name(&self) -> &'static str {
         match self {
-            Value::String(v) => format!("\"{}\"", v),
-            Value::Expression(v) => format!("{}", v),
-            Value::Bool(v) => {
-                if *v {
-                    "true".to_string()
-                } else {
-                    "false".to_string()
-                }
+            Value::Bool(_) => "Bool",
+            Value::Float(_) => "Float",
+            Value::FloatGroup(_) => "FloatGroup",
+            Value::String(_) => "String",
+            Value::Expression(_) => "Expression",
+        }
+    }
+
+    fn type_error(&self, expected: &'static str) -> ValueTypeError {
+        ValueTypeError {
+            expected,
+            found: self.variant_name(),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = ValueTypeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(v) => Ok(v),
+            _ => Err(value.type_error("Float")),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ValueTypeError;
+
+    /// Only a [`Value::Float`] holding a whole number in the range of `i64` converts
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(v) if v.fract() == 0.0 && v >= i64::MIN as f64 && v < i64::MAX as f64 => {
+                Ok(v as i64)
             }
-            Value::Float(v) => format!("{:.6}", v),
-            Value::FloatGroup(v) => format!("\"{}\"", format!("{:.6}", v.iter().format(", "))),
+            _ => Err(value.type_error("whole number Float in the range of i64")),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ValueTypeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(v) => Ok(v),
+            _ => Err(value.type_error("Bool")),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ValueTypeError;
+
+    /// Both quoted strings and bare expressions convert, as in [`Value::as_text`]
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(v) | Value::Expression(v) => Ok(v),
+            _ => Err(value.type_error("String or Expression")),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<f64> {
+    type Error = ValueTypeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::FloatGroup(v) => Ok(v),
+            _ => Err(value.type_error("FloatGroup")),
+        }
+    }
+}
+
+impl Value {
+    /// Create a [`Value::Expression`], written without quotes, such as `input`
+    pub fn expression(s: impl Into<String>) -> Self {
+        Value::Expression(s.into())
+    }
+
+    /// Create a [`Value::String`], written with quotes
+    ///
+    /// Equivalent to [`Value::from`]. It isn't named `string` because [`Value::string`] reads
+    /// the contents of an existing value.
+    pub fn quoted(s: impl Into<String>) -> Self {
+        Value::String(s.into())
+    }
+
+    /// Compare two values, allowing floats to differ slightly
+    ///
+    /// Floats, including each float of a float group, are equal if they differ by at most
+    /// `abs_tol` or by at most `rel_tol` times the larger of their magnitudes. Float groups of
+    /// different lengths are never equal. Other variants are compared exactly.
+    pub fn approx_eq(&self, other: &Value, rel_tol: f64, abs_tol: f64) -> bool {
+        let close = |a: &f64, b: &f64| {
+            a == b || (a - b).abs() <= abs_tol.max(rel_tol * a.abs().max(b.abs()))
+        };
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => close(a, b),
+            (Value::FloatGroup(a), Value::FloatGroup(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| close(a, b))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Whether every float in the value is finite, which is always true for non-float values
+    pub fn is_finite(&self) -> bool {
+        match self {
+            Value::Float(v) => v.is_finite(),
+            Value::FloatGroup(vs) => vs.iter().all(|v| v.is_finite()),
+            _ => true,
         }
     }
 
     /// Convert [`Value::Float`] to `f64` or panic
     pub fn float(&self) -> f64 {
         if let Value::Float(v) = self {
-            v.clone()
+            *v
         } else {
             panic!("Not a float")
         }
@@ -179,7 +691,7 @@ impl Value {
     /// Convert [`Value::Bool`] to `bool` or panic
     pub fn bool(&self) -> bool {
         if let Value::Bool(v) = self {
-            v.clone()
+            *v
         } else {
             panic!("Not a bool")
         }
@@ -193,11 +705,168 @@ impl Value {
             panic!("Not a float group")
         }
     }
+
+    /// Get the `f64` of a [`Value::Float`]
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::String`]
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::Expression`]
+    pub fn as_expr(&self) -> Option<&str> {
+        match self {
+            Value::Expression(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::String`] or [`Value::Expression`]
+    ///
+    /// Attributes such as `function` may be written either quoted or bare.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::String(v) | Value::Expression(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is a [`Value::String`] or [`Value::Expression`]
+    pub fn is_textual(&self) -> bool {
+        self.as_text().is_some()
+    }
+
+    /// Get the `bool` of a [`Value::Bool`]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the values of a [`Value::FloatGroup`]
+    pub fn as_float_group(&self) -> Option<&[f64]> {
+        match self {
+            Value::FloatGroup(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Append a float to a [`Value::FloatGroup`]
+    ///
+    /// Returns `false`, leaving the value unchanged, if it isn't a `FloatGroup`.
+    pub fn push_float(&mut self, v: f64) -> bool {
+        self.extend_floats(&[v])
+    }
+
+    /// Append floats to a [`Value::FloatGroup`]
+    ///
+    /// Returns `false`, leaving the value unchanged, if it isn't a `FloatGroup`.
+    pub fn extend_floats(&mut self, vs: &[f64]) -> bool {
+        match self {
+            Value::FloatGroup(v) => {
+                v.extend_from_slice(vs);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Build a [`Value`] from a JSON value
+    ///
+    /// Numbers map to [`Value::Float`], booleans to [`Value::Bool`], strings to
+    /// [`Value::String`], and arrays of numbers to [`Value::FloatGroup`]. Anything else returns
+    /// `None`.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &serde_json::Value) -> Option<Value> {
+        match json {
+            serde_json::Value::Bool(v) => Some(Value::Bool(*v)),
+            serde_json::Value::Number(v) => v.as_f64().map(Value::Float),
+            serde_json::Value::String(v) => Some(Value::String(v.clone())),
+            serde_json::Value::Array(values) => values
+                .iter()
+                .map(|v| v.as_f64())
+                .collect::<Option<Vec<f64>>>()
+                .map(Value::FloatGroup),
+            _ => None,
+        }
+    }
+}
+
+/// [`Value`] wrapper with `Eq` and `Hash`, for use as a map key
+///
+/// Floats are compared by their bits after canonicalizing them, so `-0.0` equals `0.0` and every
+/// NaN equals every other NaN.
+#[derive(Debug, Clone)]
+pub struct CanonicalValue(pub Value);
+
+// Bits of a float with the sign of zero and the NaN payload normalized
+fn canonical_bits(v: f64) -> u64 {
+    if v == 0.0 {
+        0.0f64.to_bits()
+    } else if v.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        v.to_bits()
+    }
+}
+
+impl PartialEq for CanonicalValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Value::Float(a), Value::Float(b)) => canonical_bits(*a) == canonical_bits(*b),
+            (Value::FloatGroup(a), Value::FloatGroup(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|(a, b)| canonical_bits(*a) == canonical_bits(*b))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Eq for CanonicalValue {}
+
+impl Hash for CanonicalValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(&self.0).hash(state);
+        match &self.0 {
+            Value::Bool(v) => v.hash(state),
+            Value::Float(v) => canonical_bits(*v).hash(state),
+            Value::FloatGroup(vs) => {
+                vs.len().hash(state);
+                for v in vs {
+                    canonical_bits(*v).hash(state);
+                }
+            }
+            Value::String(v) | Value::Expression(v) => v.hash(state),
+        }
+    }
+}
+
+impl From<Value> for CanonicalValue {
+    fn from(value: Value) -> Self {
+        CanonicalValue(value)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{LibertyAst, Value};
+    use super::{
+        CanonicalValue, FormatOptions, FormatStyle, GroupItem, LibertyAst, LineEnding, ParenStyle,
+        Value, ValueTypeError,
+    };
+    use std::convert::{TryFrom, TryInto};
 
     macro_rules! parse_file {
         ($fname:ident) => {{
@@ -213,9 +882,238 @@ mod test {
         parse_file!(cells_timing);
     }
 
+    #[test]
+    fn test_crlf_line_endings() {
+        let lf = include_str!("../data/cells_timing.lib");
+        let crlf = lf.replace("\r\n", "\n").replace('\n', "\r\n");
+        let lf_lib = LibertyAst::from_string(lf).unwrap().into_liberty();
+        let crlf_lib = LibertyAst::from_string(&crlf).unwrap().into_liberty();
+        assert_eq!(lf_lib, crlf_lib);
+    }
+
+    #[test]
+    fn test_write_deep() {
+        let depth = 1_000;
+        let mut item = GroupItem::Group("g".to_string(), "".to_string(), vec![]);
+        for _ in 1..depth {
+            item = GroupItem::Group("g".to_string(), "".to_string(), vec![item]);
+        }
+        let text = LibertyAst(vec![item]).to_string();
+        assert_eq!(text.matches('{').count(), depth);
+        assert_eq!(text.matches('}').count(), depth);
+        assert!(text.ends_with("}\n}"));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let ast: LibertyAst = "library(foo) {}".parse().unwrap();
+        assert_eq!(ast.0.len(), 1);
+        let err = "library(foo) {".parse::<LibertyAst>().unwrap_err();
+        assert_eq!(err.location, Some((1, 15)));
+    }
+
+    #[test]
+    fn test_unknown_statement_round_trip() {
+        let ast = LibertyAst::from_string(
+            "library(foo) {\n  foo_bar_statement xyz;\n  area : 1;\n  new_block { a b; }\n}",
+        )
+        .unwrap();
+        let items = ast.0[0].group().2;
+        assert_eq!(
+            items[0],
+            GroupItem::Unknown("foo_bar_statement xyz;".to_string())
+        );
+        assert_eq!(
+            items[2],
+            GroupItem::Unknown("new_block { a b; }".to_string())
+        );
+        let reparsed = LibertyAst::from_string(&ast.to_string()).unwrap();
+        assert_eq!(reparsed.0, ast.0);
+    }
+
+    #[test]
+    fn test_insert_attribute_after() {
+        let mut ast =
+            LibertyAst::from_string("cell(AND2) {\n  area : 1;\n  cell_footprint : and;\n}")
+                .unwrap();
+        let cell = &mut ast.0[0];
+        let drive = GroupItem::SimpleAttr("drive_strength".to_string(), Value::Float(2.0));
+        assert!(cell.insert_attribute_after("area", drive.clone()));
+        assert!(!cell.insert_attribute_after("missing", drive));
+        let names: Vec<_> = cell
+            .group()
+            .2
+            .into_iter()
+            .map(|item| match item {
+                GroupItem::SimpleAttr(name, _) => name,
+                _ => panic!("Unexpected item"),
+            })
+            .collect();
+        assert_eq!(names, vec!["area", "drive_strength", "cell_footprint"]);
+    }
+
+    #[test]
+    fn test_format_align_colons() {
+        let ast = LibertyAst::from_string(
+            r#"library(small) {
+                delay_model : table_lookup;
+                time_unit : "1ns";
+                capacitive_load_unit (1, pf);
+                cell(INV) {
+                    area : 1;
+                    cell_footprint : inv;
+                }
+            }"#,
+        )
+        .unwrap();
+        let options = FormatOptions {
+            align_colons: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(ast.format(&FormatOptions::default()), ast.to_string());
+        assert_eq!(
+            ast.format(&options),
+            "library(small) {
+delay_model : table_lookup;
+
+time_unit   : \"1ns\";
+
+capacitive_load_unit(1.000000, pf);
+
+cell(INV) {
+area           : 1.000000;
+
+cell_footprint : inv;
+
+}
+}"
+        );
+    }
+
+    #[test]
+    fn test_format_compact() {
+        let ast = LibertyAst::from_string(
+            r#"library(small) {
+                capacitive_load_unit (1, pf);
+                cell(AND2) {
+                    area : 1;
+                    /* output
+                       pin */
+                    pin(o) {
+                        direction : output;
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let options = FormatOptions {
+            style: FormatStyle::Compact,
+            ..FormatOptions::default()
+        };
+        let compact = ast.format(&options);
+        assert_eq!(
+            compact,
+            "library(small) { capacitive_load_unit(1.000000, pf); cell(AND2) { \
+             area : 1.000000; /* output pin */ pin(o) { direction : output; } } }"
+        );
+        // On a single line, the block comment becomes a trailing comment of `area`
+        let mut reparsed = LibertyAst::from_string(&compact).unwrap().into_liberty();
+        let cell = reparsed.0[0].cells.get_mut("AND2").unwrap();
+        assert_eq!(cell.comments.remove("area").unwrap(), "/* output pin */");
+        assert_eq!(reparsed, ast.into_liberty());
+    }
+
+    #[test]
+    fn test_trailing_comments() {
+        let input = "library(foo) {\n  cell(AND2) {\n    area : 1; /* x */\n    /* block */\n    \
+                     pin_order (a, b);\t/* y */\n  }\n}";
+        let ast = LibertyAst::from_string(input).unwrap();
+        let (_, _, cells) = ast.0[0].group();
+        let (_, _, items) = cells[0].group();
+        assert_eq!(items[1], GroupItem::TrailingComment("/* x */".to_string()));
+        assert_eq!(items[2], GroupItem::Comment("/* block */".to_string()));
+        let formatted = ast.to_string();
+        assert!(formatted.contains("area : 1.000000; /* x */\n"));
+        assert!(formatted.contains("\n/* block */\n"));
+        assert!(formatted.contains("); /* y */\n"));
+
+        let liberty = ast.into_liberty();
+        let cell = &liberty["foo"].cells["AND2"];
+        assert_eq!(cell.comments["area"], "/* x */");
+        assert_eq!(cell.comments["pin_order"], "/* y */");
+        assert_eq!(cell.comments.len(), 2);
+        let written = liberty.to_string();
+        assert!(written.contains("area : 1.000000; /* x */\n"));
+        assert_eq!(crate::parse_lib(&written).unwrap(), liberty);
+        assert_eq!(
+            LibertyAst::from_liberty(liberty.clone()).to_string(),
+            written
+        );
+    }
+
+    #[test]
+    fn test_paren_style() {
+        let ast = parse_file!(small);
+        let tight = ast.to_string();
+        let original = include_str!("../data/small.lib");
+        for line in &[
+            "library(foo) {",
+            "lu_table_template(delay_template_5x5) {",
+            "power_lut_template(energy_template_5x5) {",
+            " : \"1ps\";",
+        ] {
+            assert!(original.contains(line) && tight.contains(line), "{}", line);
+        }
+        assert!(!tight.contains("( "));
+        assert!(tight.contains("\ncapacitive_load_unit(1.000000, pf);\n"));
+
+        let options = FormatOptions {
+            parens: ParenStyle::Spaced,
+            ..FormatOptions::default()
+        };
+        let spaced = ast.format(&options);
+        assert!(spaced.starts_with("library ( foo ) {\n"));
+        assert!(spaced.contains("\ncapacitive_load_unit (\n1.000000, pf\n);\n"));
+        assert_eq!(
+            LibertyAst::from_string(&spaced).unwrap().into_liberty(),
+            LibertyAst::from_string(&tight).unwrap().into_liberty()
+        );
+
+        let options = FormatOptions {
+            style: FormatStyle::Compact,
+            parens: ParenStyle::Spaced,
+            ..FormatOptions::default()
+        };
+        let ast = LibertyAst::from_string("library(a) { pin_names (a, b); }").unwrap();
+        assert_eq!(ast.format(&options), "library ( a ) { pin_names (a, b); }");
+    }
+
+    #[test]
+    fn test_format_crlf() {
+        let ast =
+            LibertyAst::from_string("library(small) { area : 1; pin_names (a, b); }").unwrap();
+        let options = FormatOptions {
+            line_ending: LineEnding::CrLf,
+            ..FormatOptions::default()
+        };
+        let crlf = ast.format(&options);
+        assert_eq!(
+            crlf,
+            "library(small) {\r\narea : 1.000000;\r\n\r\npin_names(a, b);\r\n\r\n}"
+        );
+        assert_eq!(
+            crlf.replace("\r\n", "\n"),
+            ast.to_string().replace("\r\n", "\n")
+        );
+        assert_eq!(
+            LibertyAst::from_string(&crlf).unwrap().into_liberty(),
+            ast.into_liberty()
+        );
+    }
+
     #[test]
     fn test_values() {
-        assert_eq!(Value::Bool(false).bool(), false);
+        assert!(!Value::Bool(false).bool());
         assert_eq!(Value::Float(-3.45).float(), -3.45f64);
         assert_eq!(Value::Expression("A & B".to_string()).expr(), "A & B");
         assert_eq!(
@@ -224,4 +1122,198 @@ mod test {
         );
         assert_eq!(Value::String("abc def".to_string()).string(), "abc def");
     }
+
+    #[test]
+    fn test_display_non_finite() {
+        assert_eq!(Value::Float(f64::NAN).to_string(), "nan");
+        assert_eq!(
+            Value::FloatGroup(vec![1.0, f64::INFINITY, f64::NEG_INFINITY]).to_string(),
+            "\"1.000000, 1e999, -1e999\""
+        );
+        let mut liberty = crate::parse_lib("library(foo) { area : 1; }").unwrap();
+        liberty.0[0]
+            .simple_attributes
+            .insert("area".to_string(), Value::Float(f64::INFINITY));
+        assert!(liberty.to_string().contains("area : 1e999;"));
+        let ast = LibertyAst::from_liberty(liberty);
+        for style in [FormatStyle::Compact, FormatStyle::Expanded] {
+            let options = FormatOptions {
+                style,
+                ..FormatOptions::default()
+            };
+            assert!(ast.format(&options).contains("area : 1e999;"));
+        }
+    }
+
+    #[test]
+    fn test_canonical_value() {
+        use std::collections::HashSet;
+
+        let zero = CanonicalValue(Value::Float(0.0));
+        let neg_zero = CanonicalValue(Value::Float(-0.0));
+        assert_eq!(zero, neg_zero);
+        assert_eq!(
+            CanonicalValue(Value::Float(f64::NAN)),
+            CanonicalValue(Value::Float(-f64::NAN))
+        );
+        assert_ne!(zero, CanonicalValue(Value::Float(1.0)));
+        assert_ne!(zero, CanonicalValue(Value::Expression("0".to_string())));
+
+        let set: HashSet<CanonicalValue> = vec![
+            Value::Float(0.0),
+            Value::Float(-0.0),
+            Value::FloatGroup(vec![-0.0, 1.0]),
+            Value::FloatGroup(vec![0.0, 1.0]),
+        ]
+        .into_iter()
+        .map(CanonicalValue::from)
+        .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_value_accessors() {
+        assert_eq!(Value::Float(1.5).as_float(), Some(1.5));
+        assert_eq!(Value::Bool(true).as_bool(), Some(true));
+        assert_eq!(Value::String("a".to_string()).as_string(), Some("a"));
+        assert_eq!(
+            Value::Expression("A & B".to_string()).as_expr(),
+            Some("A & B")
+        );
+        assert_eq!(
+            Value::FloatGroup(vec![1.0, 2.0]).as_float_group(),
+            Some(&[1.0, 2.0][..])
+        );
+
+        assert_eq!(Value::Bool(true).as_float(), None);
+        assert_eq!(Value::Float(1.0).as_bool(), None);
+        assert_eq!(Value::Expression("a".to_string()).as_string(), None);
+        assert_eq!(Value::String("a".to_string()).as_expr(), None);
+        assert_eq!(Value::Float(1.0).as_float_group(), None);
+    }
+
+    #[test]
+    fn test_value_from() {
+        let values = [
+            (Value::from(1.5), Value::Float(1.5)),
+            (Value::from(8i64), Value::Float(8.0)),
+            (Value::from(true), Value::Bool(true)),
+            (Value::from("A&B"), Value::String("A&B".to_string())),
+            (
+                Value::from("1ns".to_string()),
+                Value::String("1ns".to_string()),
+            ),
+            (Value::quoted("1ns"), Value::String("1ns".to_string())),
+            (
+                Value::expression("input"),
+                Value::Expression("input".to_string()),
+            ),
+        ];
+        for (value, expected) in values.iter() {
+            assert_eq!(value, expected);
+            let lib = crate::parse_lib(&format!("library(foo) {{ v : {}; }}", value)).unwrap();
+            assert_eq!(&lib["foo"].simple_attributes["v"], value);
+        }
+    }
+
+    #[test]
+    fn test_value_approx_eq() {
+        let a = Value::Float(1.0);
+        assert!(a.approx_eq(&Value::Float(1.0000001), 1e-6, 0.0));
+        assert!(!a.approx_eq(&Value::Float(1.0000001), 0.0, 0.0));
+        assert!(a.approx_eq(&Value::Float(1.05), 0.0, 0.1));
+        assert!(!a.approx_eq(&Value::Float(1.2), 0.1, 0.1));
+        assert!(Value::Float(1000.0).approx_eq(&Value::Float(1001.0), 1e-3, 0.0));
+
+        let row = Value::FloatGroup(vec![1.0, 2.0]);
+        assert!(row.approx_eq(&Value::FloatGroup(vec![1.0000001, 1.9999999]), 1e-6, 0.0));
+        assert!(!row.approx_eq(&Value::FloatGroup(vec![1.0, 2.1]), 1e-6, 0.0));
+        assert!(!row.approx_eq(&Value::FloatGroup(vec![1.0]), 1.0, 1.0));
+        assert!(!row.approx_eq(&Value::FloatGroup(vec![1.0, 2.0, 3.0]), 1.0, 1.0));
+
+        let text = Value::quoted("1.0");
+        assert!(text.approx_eq(&Value::quoted("1.0"), 1.0, 1.0));
+        assert!(!text.approx_eq(&Value::expression("1.0"), 1.0, 1.0));
+        assert!(!a.approx_eq(&Value::FloatGroup(vec![1.0]), 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_value_try_from() {
+        assert_eq!(f64::try_from(Value::Float(1.5)), Ok(1.5));
+        assert_eq!(i64::try_from(Value::Float(-8.0)), Ok(-8));
+        assert_eq!(bool::try_from(Value::Bool(true)), Ok(true));
+        assert_eq!(
+            String::try_from(Value::String("1ns".to_string())),
+            Ok("1ns".to_string())
+        );
+        assert_eq!(
+            String::try_from(Value::Expression("input".to_string())),
+            Ok("input".to_string())
+        );
+        let row: Result<Vec<f64>, _> = Value::FloatGroup(vec![1.0, 2.0]).try_into();
+        assert_eq!(row, Ok(vec![1.0, 2.0]));
+
+        let text = || Value::String("a".to_string());
+        let err = f64::try_from(text()).unwrap_err();
+        assert_eq!(err.to_string(), "expected Float, found String");
+        assert_eq!(i64::try_from(text()).unwrap_err().found, "String");
+        let err = i64::try_from(Value::Float(1.5)).unwrap_err();
+        assert_eq!(err.found, "Float");
+        assert_eq!(
+            err.to_string(),
+            "expected whole number Float in the range of i64, found Float"
+        );
+        assert_eq!(i64::try_from(Value::Float(1e20)), Err(err));
+        assert_eq!(bool::try_from(text()).unwrap_err().expected, "Bool");
+        assert_eq!(
+            String::try_from(Value::Float(1.0)),
+            Err(ValueTypeError {
+                expected: "String or Expression",
+                found: "Float"
+            })
+        );
+        assert_eq!(
+            Vec::<f64>::try_from(Value::Float(1.0)).unwrap_err().found,
+            "Float"
+        );
+    }
+
+    #[test]
+    fn test_value_push_float() {
+        let mut row = Value::FloatGroup(vec![]);
+        assert!(row.push_float(1.0));
+        assert!(row.extend_floats(&[2.0, 3.0]));
+        assert!(row.push_float(4.0));
+        assert_eq!(row.as_float_group(), Some(&[1.0, 2.0, 3.0, 4.0][..]));
+
+        let mut scalar = Value::Float(1.0);
+        assert!(!scalar.push_float(2.0));
+        assert!(!scalar.extend_floats(&[2.0]));
+        assert_eq!(scalar, Value::Float(1.0));
+    }
+
+    #[test]
+    fn test_value_as_text() {
+        let quoted = Value::String("A&B".to_string());
+        let bare = Value::Expression("A&B".to_string());
+        assert_eq!(quoted.as_text(), Some("A&B"));
+        assert_eq!(bare.as_text(), Some("A&B"));
+        assert!(quoted.is_textual());
+        assert!(bare.is_textual());
+        assert_eq!(Value::Float(1.0).as_text(), None);
+        assert!(!Value::Bool(true).is_textual());
+        assert!(!Value::FloatGroup(vec![1.0]).is_textual());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_value_from_json() {
+        let json: serde_json::Value = serde_json::from_str("[0.5, 1, -2e2]").unwrap();
+        assert_eq!(
+            Value::from_json(&json),
+            Some(Value::FloatGroup(vec![0.5, 1.0, -200.0]))
+        );
+        let json: serde_json::Value = serde_json::from_str("[1, \"a\"]").unwrap();
+        assert_eq!(Value::from_json(&json), None);
+    }
 }
@@ -0,0 +1,153 @@
+//! `operating_conditions` groups, which describe the process, voltage, and temperature corners
+//! a library was characterized at
+//!
+//! ```text
+//! library(foo) {
+//!     default_operating_conditions : typical;
+//!     operating_conditions(typical) {
+//!         process : 1;
+//!         voltage : 0.9;
+//!         temperature : 25;
+//!         tree_type : balanced_tree;
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::ast::Value;
+use crate::liberty::{Group, Library};
+
+/// Corner described by an `operating_conditions` group
+#[derive(Debug, PartialEq, Clone)]
+pub struct OperatingCondition {
+    pub name: String,
+    pub process: Option<f64>,
+    pub voltage: Option<f64>,
+    pub temperature: Option<f64>,
+    /// Interconnect model, such as `balanced_tree`
+    pub tree_type: Option<String>,
+}
+
+impl OperatingCondition {
+    /// Read an `operating_conditions` group
+    ///
+    /// Returns `None` if the group is of another type. Missing or non-numeric attributes are
+    /// left as `None`.
+    pub fn from_group(group: &Group) -> Option<Self> {
+        if group.type_ != "operating_conditions" {
+            return None;
+        }
+        let float = |name| group.simple_attributes.get(name).and_then(Value::as_float);
+        Some(OperatingCondition {
+            name: group.name.clone(),
+            process: float("process"),
+            voltage: float("voltage"),
+            temperature: float("temperature"),
+            tree_type: group
+                .simple_attributes
+                .get("tree_type")
+                .and_then(Value::as_text)
+                .map(String::from),
+        })
+    }
+}
+
+fn operating_conditions(groups: &[Group]) -> Vec<OperatingCondition> {
+    groups
+        .iter()
+        .filter_map(OperatingCondition::from_group)
+        .collect()
+}
+
+fn default_operating_condition(
+    attributes: &HashMap<String, Value>,
+    groups: &[Group],
+) -> Option<OperatingCondition> {
+    let name = attributes.get("default_operating_conditions")?.as_text()?;
+    groups
+        .iter()
+        .filter(|g| g.name == name)
+        .find_map(OperatingCondition::from_group)
+}
+
+impl Group {
+    /// Read the `operating_conditions` sub-groups of a `library` group, in order
+    pub fn operating_conditions(&self) -> Vec<OperatingCondition> {
+        operating_conditions(&self.groups)
+    }
+
+    /// Find the `operating_conditions` sub-group named by the `default_operating_conditions`
+    /// attribute
+    ///
+    /// Returns `None` if the attribute is missing or names no `operating_conditions` group.
+    pub fn default_operating_condition(&self) -> Option<OperatingCondition> {
+        default_operating_condition(&self.simple_attributes, &self.groups)
+    }
+}
+
+impl Library {
+    /// Read the library's `operating_conditions` groups, see [`Group::operating_conditions`]
+    pub fn operating_conditions(&self) -> Vec<OperatingCondition> {
+        operating_conditions(&self.groups)
+    }
+
+    /// Find the library's default operating conditions, see
+    /// [`Group::default_operating_condition`]
+    pub fn default_operating_condition(&self) -> Option<OperatingCondition> {
+        default_operating_condition(&self.simple_attributes, &self.groups)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::liberty::ToGroup;
+
+    #[test]
+    fn test_operating_conditions() {
+        let liberty = crate::parse_lib(
+            "library(foo) {
+                default_operating_conditions : slow;
+                operating_conditions(typical) {
+                    process : 1;
+                    voltage : 0.9;
+                    temperature : 25;
+                    tree_type : balanced_tree;
+                }
+                operating_conditions(slow) {
+                    process : 1.2;
+                    voltage : 0.81;
+                    temperature : 125;
+                }
+            }",
+        )
+        .unwrap();
+        let lib = &liberty["foo"];
+        let conditions = lib.operating_conditions();
+        assert_eq!(
+            conditions[0],
+            OperatingCondition {
+                name: "typical".to_string(),
+                process: Some(1.0),
+                voltage: Some(0.9),
+                temperature: Some(25.0),
+                tree_type: Some("balanced_tree".to_string()),
+            }
+        );
+        assert_eq!(conditions[1].tree_type, None);
+
+        let default = lib.default_operating_condition().unwrap();
+        assert_eq!(default, conditions[1]);
+        let group = lib.clone().into_group();
+        assert_eq!(group.operating_conditions(), conditions);
+        assert_eq!(group.default_operating_condition(), Some(default));
+
+        let mut lib = lib.clone();
+        lib.simple_attributes.insert(
+            "default_operating_conditions".to_string(),
+            Value::expression("fast"),
+        );
+        assert_eq!(lib.default_operating_condition(), None);
+    }
+}
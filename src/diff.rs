@@ -0,0 +1,217 @@
+//! Structural differences between two [`Liberty`] structures
+//!
+//! Groups are matched by type and name, so formatting and attribute order don't show up as
+//! changes. Each difference is keyed by a path of groups joined with `/`, such as
+//! `library(cells)/cell(AND2)/area`. Groups that share a type and name, such as several
+//! `timing()` groups in a pin, are matched in order and get an index suffix after the first,
+//! such as `timing()[1]`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::ast::Value;
+use crate::liberty::Liberty;
+use crate::view::GroupView;
+
+/// Single difference found by [`diff`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum LibDiff {
+    /// A `cell` group exists only in the new structure
+    CellAdded(String),
+    /// A `cell` group exists only in the old structure
+    CellRemoved(String),
+    /// Any other group exists only in the new structure
+    GroupAdded(String),
+    /// Any other group exists only in the old structure
+    GroupRemoved(String),
+    /// A simple or complex attribute exists only in the new structure
+    AttrAdded(String),
+    /// A simple or complex attribute exists only in the old structure
+    AttrRemoved(String),
+    /// A simple attribute has a different value
+    AttrChanged {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+    /// A complex attribute has different values
+    ComplexAttrChanged {
+        path: String,
+        old: Vec<Value>,
+        new: Vec<Value>,
+    },
+}
+
+/// Compare two [`Liberty`] structures
+///
+/// Floats, including those in float groups, are equal if they're within `tolerance` of each
+/// other.
+pub fn diff(old: &Liberty, new: &Liberty, tolerance: f64) -> Vec<LibDiff> {
+    let old: Vec<GroupView> = old.0.iter().map(GroupView::Library).collect();
+    let new: Vec<GroupView> = new.0.iter().map(GroupView::Library).collect();
+    let mut diffs = vec![];
+    diff_subgroups("", &old, &new, tolerance, &mut diffs);
+    diffs
+}
+
+// Key each group by its path segment, adding an index to repeated type and name pairs
+fn keyed_groups<'a, 'b>(groups: &'b [GroupView<'a>]) -> BTreeMap<String, &'b GroupView<'a>> {
+    let mut seen: HashMap<(&str, &str), usize> = HashMap::new();
+    groups
+        .iter()
+        .map(|g| {
+            let (type_, name) = g.header();
+            let count = seen.entry((type_, name)).or_insert(0);
+            let key = match *count {
+                0 => format!("{}({})", type_, name),
+                n => format!("{}({})[{}]", type_, name, n),
+            };
+            *count += 1;
+            (key, g)
+        })
+        .collect()
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+fn diff_subgroups(
+    prefix: &str,
+    old: &[GroupView<'_>],
+    new: &[GroupView<'_>],
+    tolerance: f64,
+    diffs: &mut Vec<LibDiff>,
+) {
+    let old = keyed_groups(old);
+    let new = keyed_groups(new);
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let path = join(prefix, key);
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff_group(&path, old, new, tolerance, diffs),
+            (Some(old), None) if old.type_() == "cell" => diffs.push(LibDiff::CellRemoved(path)),
+            (Some(_), None) => diffs.push(LibDiff::GroupRemoved(path)),
+            (None, Some(new)) if new.type_() == "cell" => diffs.push(LibDiff::CellAdded(path)),
+            (None, Some(_)) => diffs.push(LibDiff::GroupAdded(path)),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_group(
+    path: &str,
+    old: &GroupView<'_>,
+    new: &GroupView<'_>,
+    tolerance: f64,
+    diffs: &mut Vec<LibDiff>,
+) {
+    let (old_simple, old_complex, _) = old.attributes();
+    let (new_simple, new_complex, _) = new.attributes();
+    diff_attributes(
+        path,
+        old_simple,
+        new_simple,
+        |a, b| values_equal(a, b, tolerance),
+        |path, old, new| LibDiff::AttrChanged { path, old, new },
+        diffs,
+    );
+    diff_attributes(
+        path,
+        old_complex,
+        new_complex,
+        |a, b| a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(a, b, tolerance)),
+        |path, old, new| LibDiff::ComplexAttrChanged { path, old, new },
+        diffs,
+    );
+    diff_subgroups(path, &old.children(), &new.children(), tolerance, diffs);
+}
+
+fn diff_attributes<T: Clone>(
+    path: &str,
+    old: &HashMap<String, T>,
+    new: &HashMap<String, T>,
+    equal: impl Fn(&T, &T) -> bool,
+    changed: impl Fn(String, T, T) -> LibDiff,
+    diffs: &mut Vec<LibDiff>,
+) {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        let attr_path = join(path, name);
+        match (old.get(name), new.get(name)) {
+            (Some(a), Some(b)) if !equal(a, b) => {
+                diffs.push(changed(attr_path, a.clone(), b.clone()))
+            }
+            (Some(_), Some(_)) => {}
+            (Some(_), None) => diffs.push(LibDiff::AttrRemoved(attr_path)),
+            (None, Some(_)) => diffs.push(LibDiff::AttrAdded(attr_path)),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn values_equal(a: &Value, b: &Value, tolerance: f64) -> bool {
+    a.approx_eq(b, 0.0, tolerance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn base() -> Liberty {
+        crate::parse_lib(include_str!("../data/cells.lib")).unwrap()
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        assert_eq!(diff(&base(), &base(), 0.0), vec![]);
+    }
+
+    #[test]
+    fn test_diff_changes() {
+        let old = base();
+        let mut new = base();
+        let lib = new.get_library_mut("cells").unwrap();
+        lib.cells.remove("NOR2");
+        let area = lib
+            .cells
+            .get_mut("AND2")
+            .unwrap()
+            .simple_attributes
+            .get_mut("area")
+            .unwrap();
+        let old_area = area.clone();
+        *area = Value::Float(old_area.float() + 1.0);
+        assert_eq!(
+            diff(&old, &new, 1e-9),
+            vec![
+                LibDiff::AttrChanged {
+                    path: "library(cells)/cell(AND2)/area".to_string(),
+                    old: old_area.clone(),
+                    new: Value::Float(old_area.float() + 1.0),
+                },
+                LibDiff::CellRemoved("library(cells)/cell(NOR2)".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff(&new, &old, 1e-9)[1],
+            LibDiff::CellAdded("library(cells)/cell(NOR2)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_diff_tolerance() {
+        let old = base();
+        let mut new = base();
+        new.rewrite_attribute("area", |v| {
+            if let Value::Float(area) = v {
+                *area += 1e-6;
+            }
+        });
+        assert_eq!(diff(&old, &new, 1e-3), vec![]);
+        assert_eq!(diff(&old, &new, 1e-9).len(), 4);
+    }
+}
@@ -1,9 +1,22 @@
 use nom::{
-    error::{convert_error, VerboseError},
+    error::{convert_error, VerboseError, VerboseErrorKind},
     Err,
 };
 use std::{error, fmt};
 
+use crate::parser::MAX_DEPTH_EXCEEDED;
+
+/// Broad cause of an [`Error`], for callers that handle some failures differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Input was nested deeper than [`ParseConfig::max_depth`](crate::ParseConfig::max_depth)
+    MaxDepthExceeded,
+    /// Input ended before parsing finished
+    Incomplete,
+    /// Input isn't valid Liberty syntax
+    Syntax,
+}
+
 #[derive(Debug)]
 pub struct Error<'a>(pub &'a str, pub Err<VerboseError<&'a str>>);
 
@@ -11,6 +24,36 @@ impl<'a> Error<'a> {
     pub fn new(input: &'a str, err: Err<VerboseError<&'a str>>) -> Self {
         Error(input, err)
     }
+
+    /// Get the 1-based line and column of the input where parsing failed
+    pub fn location(&self) -> Option<(usize, usize)> {
+        let remaining = match &self.1 {
+            Err::Error(err) | Err::Failure(err) => err.errors.first().map(|(rem, _)| *rem)?,
+            Err::Incomplete(_) => return None,
+        };
+        // The parser may split off parts of the input, such as trailing padding, so measure the
+        // offset by address rather than from the end
+        let offset = (remaining.as_ptr() as usize).checked_sub(self.0.as_ptr() as usize)?;
+        let consumed = self.0.get(..offset)?;
+        let line = consumed.matches('\n').count() + 1;
+        let column = offset - consumed.rfind('\n').map_or(0, |pos| pos + 1) + 1;
+        Some((line, column))
+    }
+
+    /// Get the broad cause of the failure
+    pub fn kind(&self) -> ParseErrorKind {
+        match &self.1 {
+            Err::Error(err) | Err::Failure(err) => {
+                let depth = |(_, kind): &(&str, VerboseErrorKind)| matches!(kind, VerboseErrorKind::Context(c) if *c == MAX_DEPTH_EXCEEDED);
+                if err.errors.iter().any(depth) {
+                    ParseErrorKind::MaxDepthExceeded
+                } else {
+                    ParseErrorKind::Syntax
+                }
+            }
+            Err::Incomplete(_) => ParseErrorKind::Incomplete,
+        }
+    }
 }
 
 impl<'a> fmt::Display for Error<'a> {
@@ -28,3 +71,106 @@ impl<'a> error::Error for Error<'a> {
         None
     }
 }
+
+/// Parse error that doesn't borrow the input
+///
+/// Useful where the error must be `'static`, such as the associated error of
+/// [`FromStr`](std::str::FromStr).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedError {
+    /// 1-based line and column of the input where parsing failed
+    pub location: Option<(usize, usize)>,
+    /// Description of the parse error
+    pub message: String,
+}
+
+impl<'a> From<Error<'a>> for OwnedError {
+    fn from(err: Error<'a>) -> Self {
+        OwnedError {
+            location: err.location(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for OwnedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for OwnedError {}
+
+/// Error from parsing one of several named sources
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiFileError {
+    /// Name of the source that failed to parse
+    pub source_name: String,
+    /// 1-based line within the source
+    pub line: usize,
+    /// 1-based column within the source
+    pub column: usize,
+    /// Description of the parse error
+    pub message: String,
+}
+
+impl MultiFileError {
+    pub fn new(source_name: &str, err: &Error) -> Self {
+        let (line, column) = err.location().unwrap_or((1, 1));
+        MultiFileError {
+            source_name: source_name.to_string(),
+            line,
+            column,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MultiFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.source_name, self.line, self.column, self.message
+        )
+    }
+}
+
+impl error::Error for MultiFileError {}
+
+/// Non-fatal issue found by [`parse_lib_with_warnings`](crate::parse_lib_with_warnings)
+///
+/// `path` is the chain of enclosing groups joined with `/`, such as `library(foo)/cell(AND2)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A comment inside a group was dropped
+    DroppedComment { path: String },
+    /// An unrecognized statement was kept as raw text, see [`Group::unknown`]
+    ///
+    /// [`Group::unknown`]: crate::liberty::Group::unknown
+    UnknownStatement { path: String, statement: String },
+    /// An attribute was set more than once in the same group, so only the last value was kept
+    DuplicateAttribute { path: String, name: String },
+    /// An attribute has an infinite or NaN float value
+    NonFiniteFloat { path: String, name: String },
+    /// A group has no attributes or subgroups
+    EmptyGroup { path: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DroppedComment { path } => write!(f, "{}: comment dropped", path),
+            Warning::UnknownStatement { path, statement } => {
+                write!(f, "{}: unrecognized statement '{}'", path, statement)
+            }
+            Warning::DuplicateAttribute { path, name } => {
+                write!(f, "{}: attribute '{}' overwritten", path, name)
+            }
+            Warning::NonFiniteFloat { path, name } => {
+                write!(f, "{}: attribute '{}' is not finite", path, name)
+            }
+            Warning::EmptyGroup { path } => write!(f, "{}: empty group", path),
+        }
+    }
+}
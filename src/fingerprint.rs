@@ -0,0 +1,228 @@
+//! Formatting-independent fingerprints of [`Liberty`] structures
+
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use crate::ast::{CanonicalValue, Value};
+use crate::liberty::{Cell, Group, Liberty, Library};
+use crate::view::GroupView;
+
+// 64-bit FNV-1a, used because `DefaultHasher` may change between Rust releases
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl Liberty {
+    /// Hash the structure of every library, ignoring comments and formatting
+    ///
+    /// Group types and names, attribute names, and values are hashed, with attributes sorted by
+    /// name and floats canonicalized as in [`CanonicalValue`], so `-0.0` and `0.0` hash the
+    /// same. Unlike `std`'s default hasher, the fingerprint doesn't change between runs, so it
+    /// can be stored and compared later.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.0.len().hash(&mut hasher);
+        for lib in &self.0 {
+            hash_group(&GroupView::Library(lib), false, &mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl Group {
+    /// Hash the structure of the group and everything in it, as in [`Liberty::fingerprint`]
+    ///
+    /// Sub-groups are hashed in order, so reordering them changes the fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hash_group(&GroupView::Group(self), false, &mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Library {
+    // Hash everything but the cells exactly, as in `Cell::exact_fingerprint`
+    pub(crate) fn exact_fingerprint_without_cells(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hash_node(&GroupView::Library(self), true, &mut hasher);
+        self.groups.len().hash(&mut hasher);
+        for group in &self.groups {
+            hash_group(&GroupView::Group(group), true, &mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+impl Cell {
+    /// Hash the structure of the cell, as in [`Liberty::fingerprint`]
+    ///
+    /// Equal to the [`Group::fingerprint`] of the cell as a group, with pins in name order.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hash_group(&GroupView::Cell(self), false, &mut hasher);
+        hasher.finish()
+    }
+
+    // Hash everything that's written out, including comments and the exact bits of floats, so
+    // any edit that changes the output changes the hash
+    pub(crate) fn exact_fingerprint(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hash_group(&GroupView::Cell(self), true, &mut hasher);
+        hasher.finish()
+    }
+}
+
+fn hash_group<H: Hasher>(group: &GroupView<'_>, exact: bool, state: &mut H) {
+    hash_node(group, exact, state);
+    let children = group.children();
+    children.len().hash(state);
+    for child in &children {
+        hash_group(child, exact, state);
+    }
+}
+
+// Hash the header and attributes of a group, but not its sub-groups
+fn hash_node<H: Hasher>(group: &GroupView<'_>, exact: bool, state: &mut H) {
+    let hash_value = |value: &Value, state: &mut H| {
+        if exact {
+            hash_value_exact(value, state);
+        } else {
+            CanonicalValue(value.clone()).hash(state);
+        }
+    };
+    let (type_, name) = group.header();
+    let (simple, complex, comments) = group.attributes();
+    type_.hash(state);
+    name.hash(state);
+    hash_attributes(simple, state, hash_value);
+    hash_attributes(complex, state, |values, state| {
+        values.len().hash(state);
+        for value in values {
+            hash_value(value, state);
+        }
+    });
+    if exact {
+        hash_attributes(comments, state, |comment, state| comment.hash(state));
+        group.attribute_order().0.hash(state);
+        group.unknown().hash(state);
+    }
+}
+
+fn hash_value_exact<H: Hasher>(value: &Value, state: &mut H) {
+    std::mem::discriminant(value).hash(state);
+    match value {
+        Value::String(v) | Value::Expression(v) => v.hash(state),
+        Value::Bool(v) => v.hash(state),
+        Value::Float(v) => v.to_bits().hash(state),
+        Value::FloatGroup(vs) => {
+            vs.len().hash(state);
+            for v in vs {
+                v.to_bits().hash(state);
+            }
+        }
+    }
+}
+
+fn hash_attributes<T, H: Hasher>(
+    attributes: &HashMap<String, T>,
+    state: &mut H,
+    hash_value: impl Fn(&T, &mut H),
+) {
+    let mut sorted: Vec<_> = attributes.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    sorted.len().hash(state);
+    for (name, value) in sorted {
+        name.hash(state);
+        hash_value(value, state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::Value;
+    use crate::liberty::ToGroup;
+
+    #[test]
+    fn test_fingerprint() {
+        let a = crate::parse_lib(
+            "library(foo) {
+                time_unit : \"1ns\";
+                capacitive_load_unit (1, pf);
+                cell(AND2) { area : 1; pin(o) { direction : output; } }
+                cell(INV) { area : 0; }
+            }",
+        )
+        .unwrap();
+        let b = crate::parse_lib(
+            "/* same library, reformatted */
+            library(foo) { capacitive_load_unit(1.0,pf); time_unit:\"1ns\";
+              cell(INV){area:-0.0;}
+              cell(AND2){pin(o){direction:output;} area:1.000;} }",
+        )
+        .unwrap();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        let mut c = a.clone();
+        c.rewrite_attribute("area", |v| {
+            if let Value::Float(area) = v {
+                *area += 1.0;
+            }
+        });
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        assert_ne!(
+            a.fingerprint(),
+            crate::liberty::Liberty(vec![]).fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_cell_fingerprint() {
+        let parse = |contents| crate::parse_lib(contents).unwrap().0.remove(0);
+        let a = parse(
+            r#"library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    pin(a) { direction : input; capacitance : 0.1; }
+                    pin(o) { direction : output; function : "a"; }
+                }
+            }"#,
+        );
+        let b = parse(
+            r#"library(foo) { cell(AND2) {
+              pin(o){function:"a";direction:output;}
+              pin(a){capacitance:0.10;direction:input;}
+              area:1.0; } }"#,
+        );
+        let cell = &a.cells["AND2"];
+        assert_eq!(cell.fingerprint(), b.cells["AND2"].fingerprint());
+        assert_eq!(cell.fingerprint(), cell.clone().into_group().fingerprint());
+
+        let mut edited = cell.clone();
+        edited
+            .pins
+            .get_mut("a")
+            .unwrap()
+            .simple_attributes
+            .insert("capacitance".to_string(), Value::Float(0.2));
+        assert_ne!(cell.fingerprint(), edited.fingerprint());
+    }
+}
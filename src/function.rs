@@ -0,0 +1,251 @@
+//! Boolean expressions used by pin attributes such as `function` and `three_state`
+//!
+//! Liberty boolean expressions support the following operators, in decreasing order of
+//! precedence:
+//! * `'` (postfix) and `!` (prefix): NOT
+//! * `^`: XOR
+//! * `&`, `*`, or whitespace: AND
+//! * `|` or `+`: OR
+//!
+//! The constants `0` and `1` evaluate to `false` and `true`.
+
+use std::collections::BTreeSet;
+
+use crate::ast::ParseResult;
+use crate::error::Error;
+use crate::parser::{self, ParseConfig};
+
+use nom::error::VerboseError;
+
+/// Maximum number of distinct variables compared by [`BoolExpr::is_equivalent`]
+///
+/// Equivalence is checked by enumerating every input combination, so the cost doubles with each
+/// variable.
+pub const MAX_EQUIVALENCE_VARIABLES: usize = 16;
+
+/// Parsed boolean expression
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BoolExpr {
+    /// Constant `0` or `1`
+    Const(bool),
+    /// Variable, typically a pin name
+    Var(String),
+    Not(Box<BoolExpr>),
+    And(Box<BoolExpr>, Box<BoolExpr>),
+    Or(Box<BoolExpr>, Box<BoolExpr>),
+    Xor(Box<BoolExpr>, Box<BoolExpr>),
+}
+
+/// Parse a boolean expression, such as the contents of a pin's `function` attribute
+pub fn parse_function(input: &str) -> ParseResult<'_, BoolExpr> {
+    parser::parse_function::<VerboseError<&str>>(&ParseConfig::default(), input)
+        .map_err(|e| Error::new(input, e))
+        .map(|(_, expr)| expr)
+}
+
+impl BoolExpr {
+    /// Get the names of all variables in the expression, sorted and without duplicates
+    pub fn variables(&self) -> Vec<&str> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        vars.into_iter().collect()
+    }
+
+    fn collect_variables<'a>(&'a self, vars: &mut BTreeSet<&'a str>) {
+        match self {
+            BoolExpr::Const(_) => {}
+            BoolExpr::Var(name) => {
+                vars.insert(name);
+            }
+            BoolExpr::Not(e) => e.collect_variables(vars),
+            BoolExpr::And(a, b) | BoolExpr::Or(a, b) | BoolExpr::Xor(a, b) => {
+                a.collect_variables(vars);
+                b.collect_variables(vars);
+            }
+        }
+    }
+
+    /// Format the expression in prefix notation, such as `(& A (! B))` for `A & B'`
+    ///
+    /// Constants are written as `0` and `1`.
+    pub fn to_prefix_string(&self) -> String {
+        match self {
+            BoolExpr::Const(v) => (*v as u8).to_string(),
+            BoolExpr::Var(name) => name.clone(),
+            BoolExpr::Not(e) => format!("(! {})", e.to_prefix_string()),
+            BoolExpr::And(a, b) | BoolExpr::Or(a, b) | BoolExpr::Xor(a, b) => format!(
+                "({} {} {})",
+                self.operator(),
+                a.to_prefix_string(),
+                b.to_prefix_string()
+            ),
+        }
+    }
+
+    /// Format the expression in infix notation, such as `A & !B` for `A & B'`
+    ///
+    /// Operators are written as `!`, `^`, `&`, and `|`, with parentheses only where precedence
+    /// requires them, so the string parses back to the same expression.
+    pub fn to_infix_string(&self) -> String {
+        match self {
+            BoolExpr::Const(v) => (*v as u8).to_string(),
+            BoolExpr::Var(name) => name.clone(),
+            BoolExpr::Not(e) => format!("!{}", e.infix_operand(self.precedence())),
+            BoolExpr::And(a, b) | BoolExpr::Or(a, b) | BoolExpr::Xor(a, b) => format!(
+                "{} {} {}",
+                a.infix_operand(self.precedence()),
+                self.operator(),
+                b.infix_operand(self.precedence() + 1)
+            ),
+        }
+    }
+
+    // Infix string of an operand, parenthesized if it binds looser than `min_precedence`
+    fn infix_operand(&self, min_precedence: u8) -> String {
+        if self.precedence() < min_precedence {
+            format!("({})", self.to_infix_string())
+        } else {
+            self.to_infix_string()
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            BoolExpr::Or(..) => 1,
+            BoolExpr::And(..) => 2,
+            BoolExpr::Xor(..) => 3,
+            BoolExpr::Not(_) | BoolExpr::Const(_) | BoolExpr::Var(_) => 4,
+        }
+    }
+
+    fn operator(&self) -> &'static str {
+        match self {
+            BoolExpr::Not(_) => "!",
+            BoolExpr::And(..) => "&",
+            BoolExpr::Or(..) => "|",
+            BoolExpr::Xor(..) => "^",
+            BoolExpr::Const(_) | BoolExpr::Var(_) => "",
+        }
+    }
+
+    /// Evaluate the expression, looking up each variable's value with `lookup`
+    pub fn eval<F: Fn(&str) -> bool>(&self, lookup: &F) -> bool {
+        match self {
+            BoolExpr::Const(v) => *v,
+            BoolExpr::Var(name) => lookup(name),
+            BoolExpr::Not(e) => !e.eval(lookup),
+            BoolExpr::And(a, b) => a.eval(lookup) && b.eval(lookup),
+            BoolExpr::Or(a, b) => a.eval(lookup) || b.eval(lookup),
+            BoolExpr::Xor(a, b) => a.eval(lookup) ^ b.eval(lookup),
+        }
+    }
+
+    /// Check whether two expressions are logically equivalent
+    ///
+    /// The truth tables of both expressions are compared over the union of their variables.
+    /// Returns `None` if there are more than [`MAX_EQUIVALENCE_VARIABLES`] variables, since the
+    /// answer isn't known then.
+    pub fn is_equivalent(&self, other: &BoolExpr) -> Option<bool> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        other.collect_variables(&mut vars);
+        if vars.len() > MAX_EQUIVALENCE_VARIABLES {
+            return None;
+        }
+        let vars: Vec<&str> = vars.into_iter().collect();
+        let equivalent = (0..1u32 << vars.len()).all(|bits| {
+            let lookup = |name: &str| {
+                let idx = vars.iter().position(|v| *v == name).unwrap();
+                bits & (1 << idx) != 0
+            };
+            self.eval(&lookup) == other.eval(&lookup)
+        });
+        Some(equivalent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn var(name: &str) -> Box<BoolExpr> {
+        Box::new(BoolExpr::Var(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse_function() {
+        assert_eq!(
+            parse_function("A & B").unwrap(),
+            BoolExpr::And(var("A"), var("B"))
+        );
+        assert_eq!(
+            parse_function("A B").unwrap(),
+            parse_function("A*B").unwrap()
+        );
+        assert_eq!(
+            parse_function("!A + B'").unwrap(),
+            BoolExpr::Or(
+                Box::new(BoolExpr::Not(var("A"))),
+                Box::new(BoolExpr::Not(var("B")))
+            )
+        );
+        assert_eq!(
+            parse_function("(A | B) ^ C").unwrap(),
+            BoolExpr::Xor(Box::new(BoolExpr::Or(var("A"), var("B"))), var("C"))
+        );
+        assert!(parse_function("A & ").is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(
+            parse_function("(B & A) | !B | 1").unwrap().variables(),
+            vec!["A", "B"]
+        );
+    }
+
+    #[test]
+    fn test_to_string() {
+        let expr = parse_function("A & B'").unwrap();
+        assert_eq!(expr.to_prefix_string(), "(& A (! B))");
+        assert_eq!(expr.to_infix_string(), "A & !B");
+        for input in ["(A | B) & !(C ^ 1)", "A | B & C", "A & (B & C)", "!!A"] {
+            let expr = parse_function(input).unwrap();
+            assert_eq!(parse_function(&expr.to_infix_string()).unwrap(), expr);
+        }
+        assert_eq!(
+            parse_function("(A | B) & !(C ^ 1)")
+                .unwrap()
+                .to_infix_string(),
+            "(A | B) & !(C ^ 1)"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_value(parse_function("A & B'").unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"And": [{"Var": "A"}, {"Not": {"Var": "B"}}]})
+        );
+    }
+
+    #[test]
+    fn test_is_equivalent() {
+        let and = parse_function("A & B").unwrap();
+        let equivalent = |other| and.is_equivalent(&parse_function(other).unwrap());
+        assert_eq!(equivalent("B & A"), Some(true));
+        assert_eq!(equivalent("!(!A | !B)"), Some(true));
+        assert_eq!(equivalent("A | B"), Some(false));
+        assert_eq!(equivalent("A & B & C"), Some(false));
+
+        let wide = (0..=MAX_EQUIVALENCE_VARIABLES)
+            .map(|idx| format!("V{}", idx))
+            .collect::<Vec<_>>()
+            .join(" & ");
+        let wide = parse_function(&wide).unwrap();
+        assert_eq!(wide.is_equivalent(&wide), None);
+    }
+}
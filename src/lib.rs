@@ -26,18 +26,488 @@
 //! }
 //! ```
 
+pub mod arena;
 pub mod ast;
-pub mod liberty;
+pub mod conditions;
+pub mod diff;
 mod error;
+mod fingerprint;
+pub mod function;
+pub mod liberty;
+pub mod lint;
+pub mod merge;
+pub mod node;
 mod parser;
+pub mod power;
+pub mod table;
+pub mod tracked;
+pub mod validate;
+mod view;
+pub mod visit;
+mod write;
 
 pub use ast::{ParseResult, Value};
 
-pub use error::Error;
+pub use error::{Error, MultiFileError, OwnedError, ParseErrorKind, Warning};
+pub use parser::{ParseConfig, DEFAULT_MAX_DEPTH};
+
+use std::collections::HashSet;
+
+use ast::GroupItem;
+use nom::error::{ErrorKind, VerboseError};
 
 /// Parse a string slice into a [liberty::Liberty] struct
-pub fn parse_lib(contents: &str) -> ParseResult<liberty::Liberty> {
-    Ok(liberty::Liberty::from_ast(ast::LibertyAst::from_string(
-        contents,
-    )?))
+pub fn parse_lib(contents: &str) -> ParseResult<'_, liberty::Liberty> {
+    parse_lib_with_config(contents, &ParseConfig::default())
+}
+
+/// Parse a string slice into a [liberty::Liberty] struct, relaxing the syntax as set by `config`
+///
+/// ```
+/// use liberty_parse::{parse_lib_with_config, ParseConfig};
+///
+/// let contents = "library(foo) { _internal : 1; }";
+/// assert!(parse_lib_with_config(contents, &ParseConfig::default()).is_err());
+/// let liberty = parse_lib_with_config(contents, &ParseConfig::lenient()).unwrap();
+/// assert!(liberty["foo"].simple_attributes.contains_key("_internal"));
+/// ```
+pub fn parse_lib_with_config<'a>(
+    contents: &'a str,
+    config: &ParseConfig,
+) -> ParseResult<'a, liberty::Liberty> {
+    Ok(liberty::Liberty::from_ast(
+        ast::LibertyAst::from_string_with_config(contents, config)?,
+    ))
+}
+
+/// Parse a string slice into a [liberty::Liberty] struct, keeping only the cells whose names
+/// pass `cell_filter`
+///
+/// The whole input is still parsed and checked, but `cell` groups directly inside a library are
+/// dropped from the borrowed AST before anything is copied out of `contents`. Everything else in
+/// each library is kept.
+pub fn parse_lib_filtered(
+    contents: &str,
+    cell_filter: impl Fn(&str) -> bool,
+) -> ParseResult<'_, liberty::Liberty> {
+    let libs = parse_libs_ref(contents)?
+        .into_iter()
+        .map(|lib| match lib {
+            ast::GroupItemRef::Group(type_, name, items) => ast::GroupItemRef::Group(
+                type_,
+                name,
+                items
+                    .into_iter()
+                    .filter(|item| match item {
+                        ast::GroupItemRef::Group("cell", name, _) => cell_filter(name),
+                        _ => true,
+                    })
+                    .collect(),
+            ),
+            other => other,
+        })
+        .map(ast::GroupItemRef::into_owned)
+        .collect();
+    Ok(liberty::Liberty::from_ast(ast::LibertyAst::new(libs)))
+}
+
+/// Parse a string slice, also reporting non-fatal issues as [`Warning`]s
+///
+/// Warnings cover things that don't fail parsing but are lost or likely wrong in the returned
+/// [liberty::Liberty]: dropped comments, unrecognized statements, attributes overwritten by a
+/// later one with the same name, non-finite floats, and empty groups. Floats that overflow to
+/// infinity are accepted (see [`ParseConfig::non_finite_floats`]) so they can be reported here.
+pub fn parse_lib_with_warnings(
+    contents: &str,
+) -> ParseResult<'_, (liberty::Liberty, Vec<Warning>)> {
+    let config = ParseConfig {
+        non_finite_floats: true,
+        ..ParseConfig::default()
+    };
+    let ast = ast::LibertyAst::from_string_with_config(contents, &config)?;
+    let mut warnings = vec![];
+    collect_warnings(&ast.0, "", &mut warnings);
+    Ok((liberty::Liberty::from_ast(ast), warnings))
+}
+
+// Recursively collect warnings for the items of the group at `path`
+fn collect_warnings(items: &[GroupItem], path: &str, warnings: &mut Vec<Warning>) {
+    let mut simple = HashSet::new();
+    let mut complex = HashSet::new();
+    for item in items {
+        match item {
+            GroupItem::Group(type_, name, group_items) => {
+                let path = if path.is_empty() {
+                    format!("{}({})", type_, name)
+                } else {
+                    format!("{}/{}({})", path, type_, name)
+                };
+                if group_items.is_empty() {
+                    warnings.push(Warning::EmptyGroup { path: path.clone() });
+                }
+                collect_warnings(group_items, &path, warnings);
+            }
+            GroupItem::SimpleAttr(name, value) => {
+                if !simple.insert(name) {
+                    warnings.push(Warning::DuplicateAttribute {
+                        path: path.to_string(),
+                        name: name.clone(),
+                    });
+                }
+                if !value.is_finite() {
+                    warnings.push(Warning::NonFiniteFloat {
+                        path: path.to_string(),
+                        name: name.clone(),
+                    });
+                }
+            }
+            GroupItem::ComplexAttr(name, values) => {
+                if !complex.insert(name) {
+                    warnings.push(Warning::DuplicateAttribute {
+                        path: path.to_string(),
+                        name: name.clone(),
+                    });
+                }
+                if !values.iter().all(Value::is_finite) {
+                    warnings.push(Warning::NonFiniteFloat {
+                        path: path.to_string(),
+                        name: name.clone(),
+                    });
+                }
+            }
+            // Trailing comments are kept with their attribute
+            GroupItem::TrailingComment(_) => {}
+            GroupItem::Comment(_) => warnings.push(Warning::DroppedComment {
+                path: path.to_string(),
+            }),
+            GroupItem::Unknown(statement) => warnings.push(Warning::UnknownStatement {
+                path: path.to_string(),
+                statement: statement.clone(),
+            }),
+        }
+    }
+}
+
+/// Parse a string slice into a borrowed AST
+///
+/// This allocates much less than [`parse_lib`] because names and values borrow from
+/// `contents`. Use [ast::GroupItemRef::into_owned] to convert any part of it to the owned AST.
+pub fn parse_libs_ref(contents: &str) -> ParseResult<'_, Vec<ast::GroupItemRef<'_>>> {
+    // Verbose errors allocate on every backtrack, so only use them to describe a failure
+    let config = ParseConfig::default();
+    match parser::parse_libs_ref::<(&str, ErrorKind)>(&config, contents) {
+        Ok((_, libs)) => Ok(libs),
+        Err(_) => parser::parse_libs_ref::<VerboseError<&str>>(&config, contents)
+            .map_err(|e| Error::new(contents, e))
+            .map(|(_, libs)| libs),
+    }
+}
+
+/// Parse a string slice like [`parse_lib`], parsing each top-level library on a thread pool
+///
+/// The input is split at the top-level library boundaries first, so this only helps for files
+/// with several libraries. If any library fails to parse, the whole input is parsed again
+/// sequentially so the error's location is relative to `contents`.
+#[cfg(feature = "rayon")]
+pub fn parse_lib_parallel(contents: &str) -> ParseResult<'_, liberty::Liberty> {
+    parse_lib_parallel_with_config(contents, &ParseConfig::default())
+}
+
+/// Parse a string slice like [`parse_lib_parallel`], relaxing the syntax as set by `config`
+///
+/// Every library is parsed with `config`, as is the whole input if any library fails.
+#[cfg(feature = "rayon")]
+pub fn parse_lib_parallel_with_config<'a>(
+    contents: &'a str,
+    config: &ParseConfig,
+) -> ParseResult<'a, liberty::Liberty> {
+    use rayon::prelude::*;
+
+    let libs = parser::split_libraries(contents)
+        .par_iter()
+        .map(|chunk| parser::parse_libs::<(&str, ErrorKind)>(config, chunk).map(|(_, libs)| libs))
+        .collect::<Result<Vec<_>, _>>();
+    match libs {
+        Ok(libs) => Ok(liberty::Liberty::from_ast(ast::LibertyAst::new(
+            libs.into_iter().flatten().collect(),
+        ))),
+        Err(_) => parse_lib_with_config(contents, config),
+    }
+}
+
+/// Parse several named sources into a single [liberty::Liberty] struct
+///
+/// Libraries are collected in the order of `sources`. On failure, the error names the source
+/// that failed along with the line and column within it.
+pub fn parse_concat(sources: &[(&str, &str)]) -> Result<liberty::Liberty, MultiFileError> {
+    let mut libs = vec![];
+    for (name, contents) in sources {
+        let liberty = parse_lib(contents).map_err(|e| MultiFileError::new(name, &e))?;
+        libs.extend(liberty);
+    }
+    Ok(liberty::Liberty(libs))
+}
+
+/// Parse raw bytes into a [liberty::Liberty] struct, decoding them as UTF-8 or, failing that,
+/// as Latin-1
+///
+/// Some legacy files are Latin-1 (ISO 8859-1) encoded, such as a `µ` in a comment. Every byte is
+/// a valid Latin-1 character, so any input that isn't valid UTF-8 is decoded that way rather
+/// than rejected. The error doesn't borrow the decoded text, so it's an [`OwnedError`].
+pub fn parse_lib_bytes(bytes: &[u8]) -> Result<liberty::Liberty, OwnedError> {
+    match std::str::from_utf8(bytes) {
+        Ok(contents) => parse_lib(contents).map_err(OwnedError::from),
+        Err(_) => {
+            let contents: String = bytes.iter().copied().map(char::from).collect();
+            parse_lib(&contents).map_err(OwnedError::from)
+        }
+    }
+}
+
+/// Parse a string slice, skipping malformed items instead of failing on the first one
+///
+/// A malformed attribute or group is skipped up to the next `;` or the closing `}` of its
+/// enclosing group. The returned [liberty::Liberty] contains everything that did parse, along
+/// with an error for each skipped item.
+pub fn parse_lib_lenient(contents: &str) -> (liberty::Liberty, Vec<Error<'_>>) {
+    let (libs, errors) =
+        parser::parse_libs_lenient::<VerboseError<&str>>(&ParseConfig::default(), contents);
+    (
+        liberty::Liberty::from_ast(ast::LibertyAst::new(libs)),
+        errors
+            .into_iter()
+            .map(|e| Error::new(contents, e))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn nested(depth: usize) -> String {
+        format!(
+            "library(l) {{{}{}}}",
+            "group(g) {".repeat(depth - 1),
+            "}".repeat(depth - 1)
+        )
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let contents = nested(10_000);
+        let err = parse_lib(&contents).unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::MaxDepthExceeded);
+        assert_eq!(
+            parse_lib("library(foo) {").unwrap_err().kind(),
+            ParseErrorKind::Syntax
+        );
+
+        let (libs, errors) = parse_lib_lenient(&contents);
+        assert_eq!(libs.0.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), ParseErrorKind::MaxDepthExceeded);
+
+        let contents = nested(DEFAULT_MAX_DEPTH);
+        assert!(parse_lib(&contents).is_ok());
+        let config = ParseConfig {
+            max_depth: 2,
+            ..ParseConfig::default()
+        };
+        assert_eq!(
+            parse_lib_with_config(&nested(3), &config)
+                .unwrap_err()
+                .kind(),
+            ParseErrorKind::MaxDepthExceeded
+        );
+        assert!(parse_lib_with_config(&nested(2), &config).is_ok());
+    }
+
+    #[test]
+    fn test_max_depth_parens() {
+        let parens = |depth| format!("{}A{}", "(".repeat(depth), ")".repeat(depth));
+        let kind = |input: &str| function::parse_function(input).map_err(|e| e.kind()).err();
+        assert_eq!(
+            kind(&parens(100_000)),
+            Some(ParseErrorKind::MaxDepthExceeded)
+        );
+        assert_eq!(
+            kind(&format!("{}A", "!".repeat(100_000))),
+            Some(ParseErrorKind::MaxDepthExceeded)
+        );
+        assert_eq!(kind(&parens(64)), None);
+
+        let contents = format!("library(l) {{ cell(c) {{ area : {}; }} }}", parens(100_000));
+        let err = parse_lib(&contents).unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::MaxDepthExceeded);
+    }
+
+    #[test]
+    fn test_error_location() {
+        let err = parse_lib("library(foo) {\n  area : ;\n}\n\n\n\n\n\n\n\n\n\n\n\n").unwrap_err();
+        assert_eq!(err.location(), Some((2, 10)));
+        let err = parse_lib("library(foo) {\n  area : ;é\n}\n\n").unwrap_err();
+        assert_eq!(err.location(), Some((2, 10)));
+        let err = parse_lib("library(foo) {\n  /* é */ area : ;\n}\n\n").unwrap_err();
+        assert_eq!(err.location(), Some((2, 19)));
+        assert!(err.to_string().contains("area : ;"));
+    }
+
+    #[test]
+    fn test_parse_lib_bytes() {
+        let latin1 = b"/* units in \xb5m */\nlibrary(foo) { area : 1; /* 1 \xb5m */ }";
+        let liberty = parse_lib_bytes(latin1).unwrap();
+        assert_eq!(liberty["foo"].comments["area"], "/* 1 \u{b5}m */");
+
+        let utf8 = "library(foo) { area : 1; /* 1 \u{b5}m */ }";
+        assert_eq!(parse_lib_bytes(utf8.as_bytes()).unwrap(), liberty);
+
+        let err = parse_lib_bytes(b"library(foo) { \xb5 }").unwrap_err();
+        assert_eq!(err.location, Some((1, 16)));
+    }
+
+    #[test]
+    fn test_parse_concat() {
+        let liberty =
+            parse_concat(&[("a.lib", "library(a) {}"), ("b.lib", "library(b) {}")]).unwrap();
+        assert_eq!(liberty.0.len(), 2);
+        assert_eq!(liberty.0[1].name, "b");
+
+        let err = parse_concat(&[
+            ("a.lib", "library(a) {}"),
+            (
+                "b.lib",
+                "library(b) {\n  area : 1;\n  cell(x) {\n    area : a b;\n  }\n}",
+            ),
+        ])
+        .unwrap_err();
+        assert_eq!(err.source_name, "b.lib");
+        assert_eq!(err.line, 4);
+        assert!(err.to_string().starts_with("b.lib:4:"));
+    }
+
+    #[test]
+    fn test_parse_libs_ref() {
+        let data = include_str!("../data/cells_timing.lib");
+        let libs = parse_libs_ref(data).unwrap();
+        let owned: Vec<GroupItem> = libs.iter().cloned().map(|lib| lib.into_owned()).collect();
+        assert_eq!(owned, ast::LibertyAst::from_string(data).unwrap().0);
+        match &libs[0] {
+            ast::GroupItemRef::Group(type_, name, _) => {
+                assert_eq!(*type_, "library");
+                assert!(matches!(name, std::borrow::Cow::Borrowed(_)));
+            }
+            _ => panic!("Expected a library group"),
+        }
+        assert!(parse_libs_ref("library(foo) {").is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parse_lib_parallel() {
+        let contents = [
+            include_str!("../data/small.lib"),
+            include_str!("../data/cells.lib"),
+            include_str!("../data/cells_timing.lib"),
+        ]
+        .join("\n");
+        assert_eq!(
+            parse_lib_parallel(&contents).unwrap(),
+            parse_lib(&contents).unwrap()
+        );
+
+        let err = parse_lib_parallel("library(a) {}\nlibrary(b) {\n  area : a b;\n}").unwrap_err();
+        assert_eq!(err.location().map(|(line, _)| line), Some(3));
+
+        let contents = "library(a) { _x : 1; }\nlibrary(b) { _y : 2; }";
+        assert!(parse_lib_parallel(contents).is_err());
+        let config = ParseConfig::lenient();
+        assert_eq!(
+            parse_lib_parallel_with_config(contents, &config).unwrap(),
+            parse_lib_with_config(contents, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_lib_with_warnings() {
+        let (liberty, warnings) = parse_lib_with_warnings(
+            "library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    area : 2;
+                    /* drive strength */
+                    leakage : 1e999;
+                    foo_bar_statement xyz;
+                    pin(a) {}
+                }
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            liberty["foo"].cells["AND2"].simple_attributes["area"],
+            Value::Float(2.0)
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::DuplicateAttribute {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    name: "area".to_string(),
+                },
+                Warning::DroppedComment {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                },
+                Warning::NonFiniteFloat {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    name: "leakage".to_string(),
+                },
+                Warning::UnknownStatement {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    statement: "foo_bar_statement xyz;".to_string(),
+                },
+                Warning::EmptyGroup {
+                    path: "library(foo)/cell(AND2)/pin(a)".to_string(),
+                },
+            ]
+        );
+        assert!(parse_lib_with_warnings("library(foo) {").is_err());
+    }
+
+    #[test]
+    fn test_parse_lib_filtered() {
+        let contents = include_str!("../data/cells.lib");
+        let liberty = parse_lib_filtered(contents, |name| name == "AND2").unwrap();
+        let full = parse_lib(contents).unwrap();
+        let mut lib = full.0[0].clone();
+        lib.cells.retain(|name, _| name == "AND2");
+        assert_eq!(liberty.0, vec![lib]);
+        assert_eq!(liberty.cell_count(), 1);
+
+        assert_eq!(parse_lib_filtered(contents, |_| true).unwrap(), full);
+        assert!(parse_lib_filtered("library(foo) { cell(x) {", |_| false).is_err());
+    }
+
+    #[test]
+    fn test_parse_lib_lenient() {
+        let (liberty, errors) = parse_lib_lenient(
+            "library(foo) {
+                area : a b;
+                cell(AND2) {
+                    area : 1;
+                }
+                broken_attr (1, 2;
+            }",
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].location().map(|(line, _)| line), Some(2));
+        assert_eq!(liberty.cell_count(), 1);
+        assert_eq!(
+            liberty["foo"].cells["AND2"].simple_attributes["area"],
+            Value::Float(1.0)
+        );
+
+        let (liberty, errors) = parse_lib_lenient("library(foo) { cell(x) { area : 1; }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(liberty.cell_count(), 1);
+    }
 }
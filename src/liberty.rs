@@ -6,9 +6,15 @@
 //! * `cell` and `pin` groups are brought out into [HashMap](std::collections::HashMap)s so they're
 //!   easier to work with
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
 
 use crate::ast::{GroupItem, LibertyAst, Value};
+use crate::error::OwnedError;
+use crate::view::GroupView;
 
 /// Top-level data structure of a Liberty file
 #[derive(Debug, PartialEq, Clone)]
@@ -31,6 +37,368 @@ impl Liberty {
                 .collect(),
         )
     }
+
+    /// Get the flags declared by `library_features` across all libraries
+    ///
+    /// `library_features(report_delay_calculation);` parses as a complex attribute, so this
+    /// collects the text of its values in order.
+    pub fn library_features(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|lib| lib.complex_attributes.get("library_features"))
+            .flatten()
+            .filter_map(|v| match v {
+                Value::Expression(s) | Value::String(s) => Some(s.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Apply `f` to every simple attribute named `name`, at any depth of any library
+    ///
+    /// For example, to scale every `capacitance`:
+    ///
+    /// ```
+    /// # let mut liberty = liberty_parse::liberty::Liberty(vec![]);
+    /// use liberty_parse::Value;
+    ///
+    /// liberty.rewrite_attribute("capacitance", |v| {
+    ///     if let Value::Float(c) = v {
+    ///         *c *= 1000.0;
+    ///     }
+    /// });
+    /// ```
+    pub fn rewrite_attribute<F: FnMut(&mut Value)>(&mut self, name: &str, mut f: F) {
+        for lib in self.0.iter_mut() {
+            lib.walk_attributes_mut(&mut |simple, _| {
+                if let Some(value) = simple.get_mut(name) {
+                    f(value);
+                }
+            });
+        }
+    }
+
+    /// Apply `f` to every complex attribute named `name`, at any depth of any library
+    pub fn rewrite_complex_attribute<F: FnMut(&mut Vec<Value>)>(&mut self, name: &str, mut f: F) {
+        for lib in self.0.iter_mut() {
+            lib.walk_attributes_mut(&mut |_, complex| {
+                if let Some(values) = complex.get_mut(name) {
+                    f(values);
+                }
+            });
+        }
+    }
+
+    /// Collapse each run of whitespace in every [`Value::Expression`] to a single space and
+    /// trim the ends, at any depth of any library
+    ///
+    /// For example, `A   +  B` becomes `A + B`. Quoted strings are left unchanged.
+    pub fn normalize_whitespace_in_expressions(&mut self) {
+        for lib in self.0.iter_mut() {
+            lib.walk_attributes_mut(&mut normalize_expressions);
+        }
+    }
+
+    /// Sort subgroups by name for reproducible output
+    ///
+    /// Cells and pins are stored in maps and always written in name order. This also sorts the
+    /// other subgroups of each library, cell, and pin by name with
+    /// [`Group::sort_subgroups_by_name`].
+    pub fn sort_cells(&mut self) {
+        for lib in self.0.iter_mut() {
+            sort_groups(&mut lib.groups);
+            for cell in lib.cells.values_mut() {
+                sort_groups(&mut cell.groups);
+                for pin in cell.pins.values_mut() {
+                    sort_groups(&mut pin.groups);
+                }
+            }
+        }
+    }
+
+    /// Rename the cell `old` to `new` in every library that defines it
+    ///
+    /// String and expression attributes anywhere in those libraries that mention `old` as a
+    /// whole token, such as `related_pin : "AND2";`, are rewritten too. Substrings like `AND2X1`
+    /// are left alone. Libraries that already have a cell named `new` aren't changed. Returns
+    /// whether any cell was renamed.
+    pub fn rename_cell(&mut self, old: &str, new: &str) -> bool {
+        let mut renamed = false;
+        for lib in self.0.iter_mut() {
+            if lib.cells.contains_key(new) {
+                continue;
+            }
+            let mut cell = match lib.cells.remove(old) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            cell.name = new.to_string();
+            lib.cells.insert(new.to_string(), cell);
+            let rename = |value: &mut Value| match value {
+                Value::String(s) | Value::Expression(s) => {
+                    if let Some(replaced) = replace_token(s, old, new) {
+                        *s = replaced;
+                    }
+                }
+                _ => {}
+            };
+            lib.walk_attributes_mut(&mut |simple, complex| {
+                simple.values_mut().for_each(rename);
+                complex.values_mut().flatten().for_each(rename);
+            });
+            renamed = true;
+        }
+        renamed
+    }
+
+    /// Remove cell and pin attributes that equal their library's `default_*` attribute
+    ///
+    /// For example, a pin's `fanout_load : 1;` is dropped if its library has
+    /// `default_fanout_load : 1;`, but kept if the values differ.
+    pub fn drop_default_equal_attributes(&mut self) {
+        for lib in self.0.iter_mut() {
+            let defaults: Vec<(&str, &Value)> = lib
+                .simple_attributes
+                .iter()
+                .filter_map(|(name, value)| Some((name.strip_prefix("default_")?, value)))
+                .collect();
+            let drop_defaults = |attributes: &mut HashMap<String, Value>| {
+                for (name, default) in &defaults {
+                    if attributes.get(*name) == Some(*default) {
+                        attributes.remove(*name);
+                    }
+                }
+            };
+            for cell in lib.cells.values_mut() {
+                drop_defaults(&mut cell.simple_attributes);
+                for pin in cell.pins.values_mut() {
+                    drop_defaults(&mut pin.simple_attributes);
+                }
+            }
+        }
+    }
+
+    /// Number of cells across all libraries
+    pub fn cell_count(&self) -> usize {
+        self.0.iter().map(|lib| lib.cells.len()).sum()
+    }
+
+    /// Get a library by name
+    ///
+    /// ```
+    /// use liberty_parse::parse_lib;
+    ///
+    /// let liberty = parse_lib(
+    ///     r#"
+    /// library(fast) {
+    ///     cell(AND2) { area: 1; }
+    /// }
+    /// library(slow) {
+    ///     cell(AND2) { area: 2; }
+    /// }
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let slow = liberty.get_library("slow").unwrap();
+    /// assert_eq!(slow.cells["AND2"].simple_attributes["area"].float(), 2.0);
+    /// assert_eq!(liberty["fast"].name, "fast");
+    /// assert!(liberty.get_library("typical").is_none());
+    /// ```
+    pub fn get_library(&self, name: &str) -> Option<&Library> {
+        self.0.iter().find(|lib| lib.name == name)
+    }
+
+    /// Get a mutable reference to a library by name
+    pub fn get_library_mut(&mut self, name: &str) -> Option<&mut Library> {
+        self.0.iter_mut().find(|lib| lib.name == name)
+    }
+
+    /// Remove the first library with the given name, returning it
+    pub fn remove_library(&mut self, name: &str) -> Option<Library> {
+        let idx = self.0.iter().position(|lib| lib.name == name)?;
+        Some(self.0.remove(idx))
+    }
+
+    /// Keep only the libraries for which `f` returns `true`, in their original order
+    pub fn retain_libraries<F: FnMut(&Library) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// `delay_model` of the first library
+    ///
+    /// Use [`Library::delay_model`] for files with several libraries.
+    pub fn delay_model(&self) -> Option<&str> {
+        self.0.first()?.delay_model()
+    }
+
+    /// Get the libraries, in order
+    ///
+    /// ```
+    /// let liberty = liberty_parse::parse_lib("library(a) {} library(b) {}").unwrap();
+    /// assert_eq!(liberty.libraries()[1].name, "b");
+    /// let libs = liberty.into_libraries();
+    /// assert_eq!(libs.len(), 2);
+    /// ```
+    pub fn libraries(&self) -> &[Library] {
+        &self.0
+    }
+
+    /// Consume the structure, returning its libraries in order
+    pub fn into_libraries(self) -> Vec<Library> {
+        self.0
+    }
+
+    /// Iterate over all libraries
+    pub fn iter_libraries(&self) -> impl Iterator<Item = &Library> {
+        self.0.iter()
+    }
+
+    /// Iterate over mutable references to all libraries
+    pub fn iter_libraries_mut(&mut self) -> impl Iterator<Item = &mut Library> {
+        self.0.iter_mut()
+    }
+
+    /// Clone the group at `path` within the library named `library`
+    ///
+    /// Path segments are matched as in [`Group::find_subgroup_path`], except that cell and pin
+    /// segments must be named, such as `cell:AND2` and `pin:o`. An empty path returns the whole
+    /// library as a group.
+    pub fn extract_path(&self, library: &str, path: &[&str]) -> Option<Group> {
+        let lib = self.get_library(library)?;
+        let (first, path) = match path.split_first() {
+            Some((&first, path)) => (first, path),
+            None => return Some(lib.clone().into_group()),
+        };
+        let cell = match split_segment(first) {
+            ("cell", Some(name)) => lib.cells.get(name)?,
+            _ => return find_in(&lib.groups, first, path),
+        };
+        let (first, path) = match path.split_first() {
+            Some((&first, path)) => (first, path),
+            None => return Some(cell.clone().into_group()),
+        };
+        let pin = match split_segment(first) {
+            ("pin", Some(name)) => cell.pins.get(name)?,
+            _ => return find_in(&cell.groups, first, path),
+        };
+        match path.split_first() {
+            Some((&first, path)) => find_in(&pin.groups, first, path),
+            None => Some(pin.clone().into_group()),
+        }
+    }
+
+    /// Clone the cell named `cell` within the library named `library`, as a group with all its
+    /// pins and sub-groups
+    ///
+    /// Shorthand for [`extract_path`](Liberty::extract_path) with a single `cell:` segment.
+    pub fn extract_cell(&self, library: &str, cell: &str) -> Option<Group> {
+        let cell = self.get_library(library)?.cells.get(cell)?;
+        Some(cell.clone().into_group())
+    }
+
+    /// Get a simple attribute by a dotted path, such as `foo.AND2.area`
+    ///
+    /// The path is the library name, then one segment per nested group, then the attribute
+    /// name, separated by `.`. A segment is either a group name, matching the first sub-group
+    /// with that name, or `type[name]` to pick a sub-group of a given type, such as
+    /// `operating_conditions[typical]` or `pin[A[3]]`. Cells and pins are matched before other
+    /// sub-groups. Returns `None` if any part of the path doesn't exist or it ends at a group.
+    ///
+    /// ```
+    /// let liberty = liberty_parse::parse_lib(
+    ///     "library(foo) { cell(AND2) { area : 1; pin(o) { capacitance : 0.1; } } }",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(liberty.get("foo.AND2.area").unwrap().float(), 1.0);
+    /// assert_eq!(liberty.get("foo.cell[AND2].pin[o].capacitance").unwrap().float(), 0.1);
+    /// assert!(liberty.get("foo.AND2").is_none());
+    /// ```
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        let (groups, attribute) = path.rsplit_once('.')?;
+        let mut segments = groups.split('.');
+        let lib = self.get_library(segments.next()?)?;
+        let mut node = GroupView::Library(lib);
+        for segment in segments {
+            let (type_, name) = match segment.split_once('[') {
+                Some((type_, name)) => (Some(type_), name.strip_suffix(']')?),
+                None => (None, segment),
+            };
+            node = node.children().into_iter().find(|child| {
+                let (child_type, child_name) = child.header();
+                child_name == name && type_.is_none_or(|type_| child_type == type_)
+            })?;
+        }
+        node.attributes().0.get(attribute)
+    }
+
+    /// Collect summary statistics across all libraries
+    pub fn summary(&self) -> LibrarySummary {
+        let cells = || self.0.iter().flat_map(|lib| lib.cells.values());
+        let pins = || cells().flat_map(|cell| cell.pins.values());
+        let sequential_cell_count = cells().filter(|cell| cell.is_sequential()).count();
+        let mut operating_conditions: Vec<String> = self
+            .0
+            .iter()
+            .flat_map(|lib| lib.groups.iter())
+            .filter(|g| g.type_ == "operating_conditions")
+            .map(|g| g.name.clone())
+            .collect();
+        operating_conditions.sort();
+        LibrarySummary {
+            library_count: self.0.len(),
+            cell_count: self.cell_count(),
+            sequential_cell_count,
+            combinational_cell_count: self.cell_count() - sequential_cell_count,
+            pin_count: pins().count(),
+            timing_arc_count: pins()
+                .flat_map(|pin| pin.groups.iter())
+                .filter(|g| g.type_ == "timing")
+                .count(),
+            template_count: self.template_usage().len(),
+            operating_conditions,
+        }
+    }
+}
+
+/// Summary statistics returned by [`Liberty::summary`]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LibrarySummary {
+    pub library_count: usize,
+    pub cell_count: usize,
+    /// Cells with an `ff`, `latch`, or `statetable` group
+    pub sequential_cell_count: usize,
+    pub combinational_cell_count: usize,
+    pub pin_count: usize,
+    /// Number of `timing` groups across all pins
+    pub timing_arc_count: usize,
+    /// Number of distinct table templates, see [`Liberty::template_usage`]
+    pub template_count: usize,
+    /// Names of the `operating_conditions` groups, sorted
+    pub operating_conditions: Vec<String>,
+}
+
+impl FromStr for Liberty {
+    type Err = OwnedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(crate::parse_lib(s)?)
+    }
+}
+
+impl Index<&str> for Liberty {
+    type Output = Library;
+
+    /// Get a library by name
+    ///
+    /// # Panics
+    ///
+    /// Panics if no library has the given name
+    fn index(&self, name: &str) -> &Self::Output {
+        self.get_library(name)
+            .unwrap_or_else(|| panic!("No library named '{}'", name))
+    }
 }
 
 impl IntoIterator for Liberty {
@@ -42,6 +410,64 @@ impl IntoIterator for Liberty {
     }
 }
 
+// Stable sort of the groups of each type by name, keeping the type at each position
+fn sort_groups(groups: &mut Vec<Group>) {
+    let mut by_type: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, group) in groups.iter().enumerate() {
+        by_type.entry(&group.type_).or_default().push(idx);
+    }
+    let mut order: Vec<usize> = (0..groups.len()).collect();
+    for positions in by_type.into_values() {
+        let mut sorted = positions.clone();
+        sorted.sort_by(|&a, &b| groups[a].name.cmp(&groups[b].name));
+        for (position, idx) in positions.into_iter().zip(sorted) {
+            order[position] = idx;
+        }
+    }
+    let mut taken: Vec<Option<Group>> = groups.drain(..).map(Some).collect();
+    *groups = order
+        .into_iter()
+        .map(|idx| taken[idx].take().expect("each group is placed once"))
+        .collect();
+}
+
+fn normalize_expressions(
+    simple: &mut HashMap<String, Value>,
+    complex: &mut HashMap<String, Vec<Value>>,
+) {
+    for value in simple.values_mut().chain(complex.values_mut().flatten()) {
+        if let Value::Expression(expr) = value {
+            *expr = expr.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+    }
+}
+
+// Replace whole-token occurrences of `old` in `s`, where tokens are runs of alphanumerics and
+// underscores. Returns `None` if there are none.
+fn replace_token(s: &str, old: &str, new: &str) -> Option<String> {
+    let is_token_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(s.len());
+    let mut last = 0;
+    for (start, _) in s.match_indices(old) {
+        let end = start + old.len();
+        let starts = s[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !is_token_char(c));
+        let ends = s[end..].chars().next().is_none_or(|c| !is_token_char(c));
+        if starts && ends {
+            result.push_str(&s[last..start]);
+            result.push_str(new);
+            last = end;
+        }
+    }
+    if last == 0 {
+        return None;
+    }
+    result.push_str(&s[last..]);
+    Some(result)
+}
+
 /// Top-level `library` group
 ///
 /// Every liberty file defines a `library` at the top-most level. Libraries contain
@@ -51,6 +477,12 @@ pub struct Library {
     pub name: String,
     pub simple_attributes: HashMap<String, Value>,
     pub complex_attributes: HashMap<String, Vec<Value>>,
+    /// Trailing comments of attributes, by attribute name, as in [`Group::comments`]
+    pub comments: HashMap<String, String>,
+    /// Source order of the attributes, as in [`Group::attribute_order`]
+    pub attribute_order: AttributeOrder,
+    /// Unrecognized statements as raw text, as in [`Group::unknown`]
+    pub unknown: Vec<String>,
     pub groups: Vec<Group>,
     pub cells: HashMap<String, Cell>,
 }
@@ -61,10 +493,50 @@ impl Library {
             name: name.to_string(),
             simple_attributes: HashMap::new(),
             complex_attributes: HashMap::new(),
+            comments: HashMap::new(),
+            attribute_order: AttributeOrder::default(),
+            unknown: vec![],
             groups: vec![],
             cells: HashMap::new(),
         }
     }
+
+    /// Value of the library's `delay_model` attribute, such as `table_lookup`
+    pub fn delay_model(&self) -> Option<&str> {
+        self.simple_attributes.get("delay_model")?.as_text()
+    }
+
+    /// Value of the library's `date` attribute
+    pub fn date(&self) -> Option<&str> {
+        self.simple_attributes.get("date")?.as_text()
+    }
+
+    /// Value of the library's `revision` attribute, see [`Group::revision`]
+    pub fn revision(&self) -> Option<String> {
+        revision(&self.simple_attributes)
+    }
+
+    /// Argument of the library's `technology` attribute, see [`Group::technology`]
+    pub fn technology(&self) -> Option<&str> {
+        self.complex_attributes
+            .get("technology")?
+            .first()?
+            .as_text()
+    }
+
+    // Apply `f` to the attribute maps of the library and every group nested inside it
+    pub(crate) fn walk_attributes_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut HashMap<String, Value>, &mut HashMap<String, Vec<Value>>),
+    {
+        f(&mut self.simple_attributes, &mut self.complex_attributes);
+        for group in self.groups.iter_mut() {
+            group.walk_attributes_mut(f);
+        }
+        for cell in self.cells.values_mut() {
+            cell.walk_attributes_mut(f);
+        }
+    }
 }
 
 /// General group struct
@@ -76,6 +548,20 @@ pub struct Group {
     pub name: String,
     pub simple_attributes: HashMap<String, Value>,
     pub complex_attributes: HashMap<String, Vec<Value>>,
+    /// Trailing comments of attributes, by attribute name
+    ///
+    /// A comment on the same line as an attribute, such as `/* x */` in `area : 1; /* x */`,
+    /// is kept here and written back after the attribute. Other comments are dropped.
+    pub comments: HashMap<String, String>,
+    /// Source order of the simple and complex attributes, which the maps don't keep
+    ///
+    /// Attributes are iterated and written in this order. Ones missing from it, such as
+    /// attributes inserted into the maps directly, come after the listed ones in name order.
+    pub attribute_order: AttributeOrder,
+    /// Unrecognized statements, such as `foo_bar_statement xyz;`, as raw text in file order
+    ///
+    /// They're written back after the attributes.
+    pub unknown: Vec<String>,
     pub groups: Vec<Group>,
 }
 
@@ -87,6 +573,9 @@ impl Group {
             name: name.to_string(),
             simple_attributes: HashMap::new(),
             complex_attributes: HashMap::new(),
+            comments: HashMap::new(),
+            attribute_order: AttributeOrder::default(),
+            unknown: vec![],
             groups: vec![],
         }
     }
@@ -96,48 +585,444 @@ impl Group {
         let (type_, name, items) = group_item.group();
         let mut simple_attributes: HashMap<String, Value> = HashMap::new();
         let mut complex_attributes: HashMap<String, Vec<Value>> = HashMap::new();
+        let mut comments: HashMap<String, String> = HashMap::new();
+        let mut attribute_order = AttributeOrder::default();
+        let mut unknown: Vec<String> = vec![];
         let mut groups: Vec<Self> = vec![];
+        let mut last_attribute = None;
         for item in items {
             match item {
                 GroupItem::SimpleAttr(name, value) => {
-                    simple_attributes.insert(name, value);
+                    last_attribute = Some(name.clone());
+                    if simple_attributes.insert(name.clone(), value).is_none() {
+                        attribute_order.0.push(name);
+                    }
+                    continue;
                 }
                 GroupItem::ComplexAttr(name, value) => {
-                    complex_attributes.insert(name, value);
+                    last_attribute = Some(name.clone());
+                    if complex_attributes.insert(name.clone(), value).is_none() {
+                        attribute_order.0.push(name);
+                    }
+                    continue;
+                }
+                GroupItem::TrailingComment(comment) => {
+                    if let Some(name) = last_attribute.take() {
+                        comments.insert(name, comment);
+                    }
                 }
                 GroupItem::Group(type_, name, items) => {
                     groups.push(Group::from_group_item(GroupItem::Group(type_, name, items)));
                 }
+                GroupItem::Unknown(statement) => unknown.push(statement),
                 _ => {}
             }
+            last_attribute = None;
         }
         Self {
-            name: name,
-            type_: type_,
-            simple_attributes: simple_attributes,
-            complex_attributes: complex_attributes,
-            groups: groups,
+            name,
+            type_,
+            simple_attributes,
+            complex_attributes,
+            comments,
+            attribute_order,
+            unknown,
+            groups,
         }
     }
 
     /// Convert a [Liberty] struct into a [GroupItem::Group] variant
-    pub fn into_group_item(self) -> GroupItem {
+    pub fn into_group_item(mut self) -> GroupItem {
         let mut items: Vec<GroupItem> = Vec::with_capacity(
-            self.simple_attributes.len() + self.complex_attributes.len() + self.groups.len(),
-        );
-        items.extend(
-            self.simple_attributes
-                .into_iter()
-                .map(|(name, value)| GroupItem::SimpleAttr(name, value)),
-        );
-        items.extend(
-            self.complex_attributes
-                .into_iter()
-                .map(|(name, value)| GroupItem::ComplexAttr(name, value)),
+            self.simple_attributes.len()
+                + self.complex_attributes.len()
+                + self.unknown.len()
+                + self.groups.len(),
         );
+        let mut attributes: Vec<(String, GroupItem)> = self
+            .simple_attributes
+            .into_iter()
+            .map(|(name, value)| (name.clone(), GroupItem::SimpleAttr(name, value)))
+            .chain(
+                self.complex_attributes
+                    .into_iter()
+                    .map(|(name, values)| (name.clone(), GroupItem::ComplexAttr(name, values))),
+            )
+            .collect();
+        self.attribute_order
+            .sort(&mut attributes, |(name, _)| name.as_str());
+        for (name, item) in attributes {
+            items.push(item);
+            items.extend(self.comments.remove(&name).map(GroupItem::TrailingComment));
+        }
+        items.extend(self.unknown.into_iter().map(GroupItem::Unknown));
         items.extend(self.groups.into_iter().map(|g| g.into_group_item()));
         GroupItem::Group(self.type_, self.name, items)
     }
+
+    /// Get a simple attribute by name
+    pub fn simple_attribute(&self, name: &str) -> Option<&Value> {
+        self.simple_attributes.get(name)
+    }
+
+    /// Get a simple attribute by name, ignoring ASCII case
+    pub fn simple_attribute_ci(&self, name: &str) -> Option<&Value> {
+        self.simple_attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    /// Get a complex attribute by name
+    pub fn complex_attribute(&self, name: &str) -> Option<&Vec<Value>> {
+        self.complex_attributes.get(name)
+    }
+
+    /// Get a complex attribute by name, ignoring ASCII case
+    pub fn complex_attribute_ci(&self, name: &str) -> Option<&Vec<Value>> {
+        self.complex_attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    /// Iterate over the sub-groups with a given type
+    pub fn iter_subgroups_of_type<'a>(&'a self, type_: &'a str) -> impl Iterator<Item = &'a Group> {
+        self.groups.iter().filter(move |g| g.type_ == type_)
+    }
+
+    /// Get an iterator over sub-groups of type `type_` at any depth, in depth-first order
+    ///
+    /// Matching groups are searched too, so a group of type `type_` nested in another is also
+    /// yielded.
+    pub fn iter_descendants_of_type<'a>(
+        &'a self,
+        type_: &'a str,
+    ) -> impl Iterator<Item = &'a Group> {
+        let mut stack = vec![self.groups.iter()];
+        std::iter::from_fn(move || loop {
+            let group = match stack.last_mut()?.next() {
+                Some(group) => group,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            stack.push(group.groups.iter());
+            if group.type_ == type_ {
+                return Some(group);
+            }
+        })
+    }
+
+    /// Sort the sub-groups of each type by name, leaving attributes and nested groups alone
+    ///
+    /// The sort is stable, and each position keeps its group type, so in
+    /// `cell(B) timing() cell(A)` the cells swap places around the `timing` group.
+    pub fn sort_subgroups_by_name(&mut self) {
+        sort_groups(&mut self.groups);
+    }
+
+    /// Iterate over the `pin` sub-groups
+    pub fn iter_pins(&self) -> impl Iterator<Item = &Group> {
+        self.iter_subgroups_of_type("pin")
+    }
+
+    /// Get the pin direction from the `direction` attribute
+    pub fn pin_direction(&self) -> Option<PinDirection> {
+        self.simple_attributes
+            .get("direction")
+            .and_then(PinDirection::from_value)
+    }
+
+    /// Get the pin's `capacitance` attribute
+    pub fn capacitance(&self) -> Option<f64> {
+        self.simple_attributes.get("capacitance")?.as_float()
+    }
+
+    /// Get the pin's `function` attribute, such as `A & B`
+    ///
+    /// Use [`BoolExpr`](crate::function::BoolExpr) to parse the function.
+    pub fn function(&self) -> Option<&str> {
+        self.simple_attributes.get("function")?.as_text()
+    }
+
+    /// Count the input, output, and inout pins of a cell group
+    ///
+    /// Pins are classified by their `direction`, and those without one or with `internal`
+    /// direction aren't counted.
+    pub fn pin_counts(&self) -> (usize, usize, usize) {
+        self.iter_pins()
+            .fold((0, 0, 0), |(inputs, outputs, inouts), pin| {
+                match pin.pin_direction() {
+                    Some(PinDirection::Input) => (inputs + 1, outputs, inouts),
+                    Some(PinDirection::Output) => (inputs, outputs + 1, inouts),
+                    Some(PinDirection::Inout) => (inputs, outputs, inouts + 1),
+                    Some(PinDirection::Internal) | None => (inputs, outputs, inouts),
+                }
+            })
+    }
+
+    /// Find a nested sub-group by path
+    ///
+    /// Each path segment is either `type:name`, matching a sub-group's type and name, or `type`,
+    /// matching the first sub-group of that type. An empty path returns this group.
+    pub fn find_subgroup_path(&self, path: &[&str]) -> Option<&Group> {
+        path.iter()
+            .try_fold(self, |group, segment| find_segment(&group.groups, segment))
+    }
+
+    /// Get the `pin(A[3])` group of bit `index` of the `bus(A)` sub-group named `bus`
+    pub fn bus_pin(&self, bus: &str, index: usize) -> Option<&Group> {
+        let name = format!("{}[{}]", bus, index);
+        self.groups
+            .iter()
+            .find(|g| g.type_ == "bus" && g.name == bus)?
+            .groups
+            .iter()
+            .find(|g| g.type_ == "pin" && g.name == name)
+    }
+
+    /// Iterate over the sub-groups with a given type, ignoring ASCII case
+    pub fn iter_subgroups_of_type_ci<'a>(
+        &'a self,
+        type_: &'a str,
+    ) -> impl Iterator<Item = &'a Group> {
+        self.groups
+            .iter()
+            .filter(move |g| g.type_.eq_ignore_ascii_case(type_))
+    }
+
+    /// Remove the first sub-group with the given type and name
+    pub fn remove_subgroup(&mut self, type_: &str, name: &str) -> Option<Group> {
+        let idx = self
+            .groups
+            .iter()
+            .position(|g| g.type_ == type_ && g.name == name)?;
+        Some(self.groups.remove(idx))
+    }
+
+    /// Remove the sub-group at `idx`, keeping the order of the remaining sub-groups
+    pub fn remove_subgroup_at(&mut self, idx: usize) -> Option<Group> {
+        if idx < self.groups.len() {
+            Some(self.groups.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Clone the group with only the listed attributes and sub-group types
+    ///
+    /// Both lists apply at every depth: kept sub-groups are filtered the same way. Unrecognized
+    /// statements are dropped.
+    pub fn clone_filtered(&self, keep_attrs: &[&str], keep_subgroup_types: &[&str]) -> Group {
+        let keep = |name: &String| keep_attrs.contains(&name.as_str());
+        Group {
+            type_: self.type_.clone(),
+            name: self.name.clone(),
+            simple_attributes: self
+                .simple_attributes
+                .iter()
+                .filter(|(name, _)| keep(name))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            complex_attributes: self
+                .complex_attributes
+                .iter()
+                .filter(|(name, _)| keep(name))
+                .map(|(name, values)| (name.clone(), values.clone()))
+                .collect(),
+            comments: self
+                .comments
+                .iter()
+                .filter(|(name, _)| keep(name))
+                .map(|(name, comment)| (name.clone(), comment.clone()))
+                .collect(),
+            attribute_order: AttributeOrder(
+                self.attribute_order
+                    .0
+                    .iter()
+                    .filter(|name| keep(name))
+                    .cloned()
+                    .collect(),
+            ),
+            unknown: vec![],
+            groups: self
+                .groups
+                .iter()
+                .filter(|g| keep_subgroup_types.contains(&g.type_.as_str()))
+                .map(|g| g.clone_filtered(keep_attrs, keep_subgroup_types))
+                .collect(),
+        }
+    }
+
+    /// Value of a library group's `date` attribute
+    pub fn date(&self) -> Option<&str> {
+        self.simple_attribute("date")?.as_text()
+    }
+
+    /// Value of a library group's `revision` attribute
+    ///
+    /// Revisions are written both quoted and as bare numbers, such as `revision : 1.2;`, so
+    /// numbers are formatted without trailing zeros.
+    pub fn revision(&self) -> Option<String> {
+        revision(&self.simple_attributes)
+    }
+
+    /// Argument of a library group's `technology` complex attribute, such as `cmos` for
+    /// `technology (cmos);`
+    pub fn technology(&self) -> Option<&str> {
+        self.complex_attribute("technology")?.first()?.as_text()
+    }
+
+    /// Iterate over the simple and complex attributes together, in source order
+    ///
+    /// See [`Group::attribute_order`] for attributes added after parsing.
+    pub fn iter_attributes(&self) -> impl Iterator<Item = (&String, Attribute<'_>)> {
+        let mut attributes: Vec<_> = self
+            .simple_attributes
+            .iter()
+            .map(|(name, value)| (name, Attribute::Simple(value)))
+            .chain(
+                self.complex_attributes
+                    .iter()
+                    .map(|(name, values)| (name, Attribute::Complex(values))),
+            )
+            .collect();
+        self.attribute_order
+            .sort(&mut attributes, |(name, _)| name.as_str());
+        attributes.into_iter()
+    }
+
+    /// Iterate over mutable references to the simple and complex attributes, in source order
+    pub fn iter_attributes_mut(&mut self) -> impl Iterator<Item = (&String, AttributeMut<'_>)> {
+        let order = &self.attribute_order;
+        let mut attributes: Vec<_> = self
+            .simple_attributes
+            .iter_mut()
+            .map(|(name, value)| (name, AttributeMut::Simple(value)))
+            .chain(
+                self.complex_attributes
+                    .iter_mut()
+                    .map(|(name, values)| (name, AttributeMut::Complex(values))),
+            )
+            .collect();
+        order.sort(&mut attributes, |(name, _)| name.as_str());
+        attributes.into_iter()
+    }
+
+    /// Insert an attribute right after the attribute named `anchor`
+    ///
+    /// An attribute already named `name` is replaced and moved. Returns `false`, leaving the
+    /// group unchanged, if it has no simple or complex attribute named `anchor`.
+    pub fn insert_attribute_after(
+        &mut self,
+        anchor: &str,
+        name: &str,
+        attribute: AttributeValue,
+    ) -> bool {
+        if !self.simple_attributes.contains_key(anchor)
+            && !self.complex_attributes.contains_key(anchor)
+        {
+            return false;
+        }
+        if name != anchor {
+            let mut order: Vec<String> = self
+                .iter_attributes()
+                .map(|(listed, _)| listed.clone())
+                .filter(|listed| listed != name)
+                .collect();
+            let idx = order
+                .iter()
+                .position(|listed| listed == anchor)
+                .unwrap_or(0);
+            order.insert(idx + 1, name.to_string());
+            self.attribute_order = AttributeOrder(order);
+        }
+        match attribute {
+            AttributeValue::Simple(value) => {
+                self.simple_attributes.insert(name.to_string(), value);
+            }
+            AttributeValue::Complex(values) => {
+                self.complex_attributes.insert(name.to_string(), values);
+            }
+        }
+        true
+    }
+
+    /// Number of simple and complex attributes directly in this group
+    pub fn attribute_count(&self) -> usize {
+        self.simple_attributes.len() + self.complex_attributes.len()
+    }
+
+    /// Number of sub-groups directly in this group
+    pub fn subgroup_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Number of sub-groups nested at any depth in this group
+    pub fn total_group_count(&self) -> usize {
+        self.groups.iter().map(|g| 1 + g.total_group_count()).sum()
+    }
+
+    /// Collapse whitespace in the expressions of this group and every group nested inside it,
+    /// see [`Liberty::normalize_whitespace_in_expressions`]
+    pub fn normalize_whitespace_in_expressions(&mut self) {
+        self.walk_attributes_mut(&mut normalize_expressions);
+    }
+
+    // Apply `f` to the attribute maps of the group and every group nested inside it
+    pub(crate) fn walk_attributes_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut HashMap<String, Value>, &mut HashMap<String, Vec<Value>>),
+    {
+        f(&mut self.simple_attributes, &mut self.complex_attributes);
+        for group in self.groups.iter_mut() {
+            group.walk_attributes_mut(f);
+        }
+    }
+}
+
+impl Index<&str> for Group {
+    type Output = Value;
+
+    /// Get a simple attribute by name
+    ///
+    /// Use [`Group::simple_attribute`] where the attribute may be missing.
+    ///
+    /// ```
+    /// use liberty_parse::{liberty::Group, Value};
+    ///
+    /// let mut cell = Group::new("cell", "AND2");
+    /// cell.simple_attributes.insert("area".to_string(), Value::Float(1.0));
+    ///
+    /// if cell.simple_attribute("area").is_some() {
+    ///     assert_eq!(cell["area"].float(), 1.0);
+    /// }
+    /// cell["area"] = Value::Float(2.0);
+    /// assert_eq!(cell["area"], Value::Float(2.0));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group has no simple attribute with the given name
+    fn index(&self, name: &str) -> &Self::Output {
+        self.simple_attribute(name)
+            .unwrap_or_else(|| panic!("No attribute named '{}'", name))
+    }
+}
+
+impl IndexMut<&str> for Group {
+    /// Get a mutable reference to a simple attribute by name
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group has no simple attribute with the given name
+    fn index_mut(&mut self, name: &str) -> &mut Self::Output {
+        self.simple_attributes
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("No attribute named '{}'", name))
+    }
 }
 
 /// `cell` group of a [Library](Library)
@@ -146,6 +1031,12 @@ pub struct Cell {
     pub name: String,
     pub simple_attributes: HashMap<String, Value>,
     pub complex_attributes: HashMap<String, Vec<Value>>,
+    /// Trailing comments of attributes, by attribute name, as in [`Group::comments`]
+    pub comments: HashMap<String, String>,
+    /// Source order of the attributes, as in [`Group::attribute_order`]
+    pub attribute_order: AttributeOrder,
+    /// Unrecognized statements as raw text, as in [`Group::unknown`]
+    pub unknown: Vec<String>,
     pub groups: Vec<Group>,
     pub pins: HashMap<String, Pin>,
 }
@@ -157,10 +1048,34 @@ impl Cell {
             name: name.to_string(),
             simple_attributes: HashMap::new(),
             complex_attributes: HashMap::new(),
+            comments: HashMap::new(),
+            attribute_order: AttributeOrder::default(),
+            unknown: vec![],
             groups: vec![],
             pins: HashMap::new(),
         }
     }
+
+    /// Whether the cell has an `ff`, `latch`, or `statetable` group
+    pub fn is_sequential(&self) -> bool {
+        self.groups
+            .iter()
+            .any(|g| matches!(g.type_.as_str(), "ff" | "latch" | "statetable"))
+    }
+
+    // Apply `f` to the attribute maps of the cell and every group nested inside it
+    pub(crate) fn walk_attributes_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut HashMap<String, Value>, &mut HashMap<String, Vec<Value>>),
+    {
+        f(&mut self.simple_attributes, &mut self.complex_attributes);
+        for group in self.groups.iter_mut() {
+            group.walk_attributes_mut(f);
+        }
+        for pin in self.pins.values_mut() {
+            pin.walk_attributes_mut(f);
+        }
+    }
 }
 
 /// `pin` group of a [Cell](Cell)
@@ -169,6 +1084,12 @@ pub struct Pin {
     pub name: String,
     pub simple_attributes: HashMap<String, Value>,
     pub complex_attributes: HashMap<String, Vec<Value>>,
+    /// Trailing comments of attributes, by attribute name, as in [`Group::comments`]
+    pub comments: HashMap<String, String>,
+    /// Source order of the attributes, as in [`Group::attribute_order`]
+    pub attribute_order: AttributeOrder,
+    /// Unrecognized statements as raw text, as in [`Group::unknown`]
+    pub unknown: Vec<String>,
     pub groups: Vec<Group>,
 }
 
@@ -179,9 +1100,206 @@ impl Pin {
             name: name.to_string(),
             simple_attributes: HashMap::new(),
             complex_attributes: HashMap::new(),
+            comments: HashMap::new(),
+            attribute_order: AttributeOrder::default(),
+            unknown: vec![],
             groups: vec![],
         }
     }
+
+    /// Get the pin direction from the `direction` attribute, like [`Group::pin_direction`]
+    pub fn pin_direction(&self) -> Option<PinDirection> {
+        self.simple_attributes
+            .get("direction")
+            .and_then(PinDirection::from_value)
+    }
+
+    /// Get the pin's `capacitance` attribute
+    pub fn capacitance(&self) -> Option<f64> {
+        self.simple_attributes.get("capacitance")?.as_float()
+    }
+
+    /// Get the pin's `function` attribute, see [`Group::function`]
+    pub fn function(&self) -> Option<&str> {
+        self.simple_attributes.get("function")?.as_text()
+    }
+
+    /// Check whether the pin is flagged with `clock : true;`
+    pub fn is_clock(&self) -> bool {
+        self.simple_attributes.get("clock").is_some_and(is_flag_set)
+    }
+
+    /// Get the pin's role in a clock-gating cell, if any
+    ///
+    /// The role is read from the `clock_gate_*_pin` boolean attributes.
+    pub fn clock_gate_role(&self) -> Option<ClockGateRole> {
+        [
+            ("clock_gate_clock_pin", ClockGateRole::Clock),
+            ("clock_gate_enable_pin", ClockGateRole::Enable),
+            ("clock_gate_test_pin", ClockGateRole::Test),
+            ("clock_gate_obs_pin", ClockGateRole::Observability),
+            ("clock_gate_out_pin", ClockGateRole::Out),
+        ]
+        .iter()
+        .find(|(attr, _)| self.simple_attributes.get(*attr).is_some_and(is_flag_set))
+        .map(|(_, role)| *role)
+    }
+
+    // Apply `f` to the attribute maps of the pin and every group nested inside it
+    pub(crate) fn walk_attributes_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut HashMap<String, Value>, &mut HashMap<String, Vec<Value>>),
+    {
+        f(&mut self.simple_attributes, &mut self.complex_attributes);
+        for group in self.groups.iter_mut() {
+            group.walk_attributes_mut(f);
+        }
+    }
+}
+
+/// Names of a group's attributes in source order, see [`Group::attribute_order`]
+///
+/// Ignored when comparing groups, so groups with the same attributes are equal whatever order
+/// they were written in.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeOrder(pub Vec<String>);
+
+impl AttributeOrder {
+    // Sort `items` by the position of their name in the order, putting unlisted names last in
+    // name order
+    pub(crate) fn sort<T>(&self, items: &mut [T], name: impl Fn(&T) -> &str) {
+        let mut positions: HashMap<&str, usize> = HashMap::with_capacity(self.0.len());
+        for (idx, listed) in self.0.iter().enumerate() {
+            positions.entry(listed).or_insert(idx);
+        }
+        let key = |item: &T| {
+            let name = name(item);
+            let position = positions.get(name).copied().unwrap_or(usize::MAX);
+            (position, name.to_string())
+        };
+        items.sort_by_cached_key(key);
+    }
+}
+
+impl PartialEq for AttributeOrder {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Simple or complex attribute, as yielded by [`Group::iter_attributes`]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Attribute<'a> {
+    Simple(&'a Value),
+    Complex(&'a [Value]),
+}
+
+impl<'a> Attribute<'a> {
+    /// Get the value of an [`Attribute::Simple`]
+    pub fn as_simple(&self) -> Option<&'a Value> {
+        match self {
+            Attribute::Simple(value) => Some(value),
+            Attribute::Complex(_) => None,
+        }
+    }
+
+    /// Get the values of an [`Attribute::Complex`]
+    pub fn as_complex(&self) -> Option<&'a [Value]> {
+        match self {
+            Attribute::Complex(values) => Some(values),
+            Attribute::Simple(_) => None,
+        }
+    }
+}
+
+/// Owned simple or complex attribute, as taken by [`Group::insert_attribute_after`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum AttributeValue {
+    Simple(Value),
+    Complex(Vec<Value>),
+}
+
+/// Mutable simple or complex attribute, as yielded by [`Group::iter_attributes_mut`]
+#[derive(Debug, PartialEq)]
+pub enum AttributeMut<'a> {
+    Simple(&'a mut Value),
+    Complex(&'a mut Vec<Value>),
+}
+
+/// Role of a pin in a clock-gating cell
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ClockGateRole {
+    /// `clock_gate_clock_pin : true;`
+    Clock,
+    /// `clock_gate_enable_pin : true;`
+    Enable,
+    /// `clock_gate_test_pin : true;`
+    Test,
+    /// `clock_gate_obs_pin : true;`
+    Observability,
+    /// `clock_gate_out_pin : true;`
+    Out,
+}
+
+/// Value of a pin's `direction` attribute
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PinDirection {
+    Input,
+    Output,
+    Inout,
+    Internal,
+}
+
+impl PinDirection {
+    /// Read a direction from a quoted or bare value, such as `direction : input;`
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value.as_text()? {
+            "input" => Some(PinDirection::Input),
+            "output" => Some(PinDirection::Output),
+            "inout" => Some(PinDirection::Inout),
+            "internal" => Some(PinDirection::Internal),
+            _ => None,
+        }
+    }
+}
+
+// Split a path segment into its type and optional name
+fn split_segment(segment: &str) -> (&str, Option<&str>) {
+    match segment.split_once(':') {
+        Some((type_, name)) => (type_, Some(name)),
+        None => (segment, None),
+    }
+}
+
+// Find the first group matching a path segment
+fn find_segment<'a>(groups: &'a [Group], segment: &str) -> Option<&'a Group> {
+    let (type_, name) = split_segment(segment);
+    groups
+        .iter()
+        .find(|g| g.type_ == type_ && name.is_none_or(|name| g.name == name))
+}
+
+// Clone the group at `first` followed by `path` within `groups`
+fn find_in(groups: &[Group], first: &str, path: &[&str]) -> Option<Group> {
+    find_segment(groups, first)?
+        .find_subgroup_path(path)
+        .cloned()
+}
+
+fn revision(attributes: &HashMap<String, Value>) -> Option<String> {
+    match attributes.get("revision")? {
+        Value::Float(v) => Some(v.to_string()),
+        value => value.as_text().map(String::from),
+    }
+}
+
+// Boolean flags parse as `Value::Bool`, but some files quote them or use other casing
+fn is_flag_set(value: &Value) -> bool {
+    match value {
+        Value::Bool(v) => *v,
+        Value::Expression(v) | Value::String(v) => v.eq_ignore_ascii_case("true"),
+        _ => false,
+    }
 }
 
 /// Convert a general Group into a more specific type
@@ -207,7 +1325,10 @@ impl FromGroup for Library {
             name: group.name,
             simple_attributes: group.simple_attributes,
             complex_attributes: group.complex_attributes,
-            groups: groups,
+            comments: group.comments,
+            attribute_order: group.attribute_order,
+            unknown: group.unknown,
+            groups,
             cells: cells.into_iter().fold(HashMap::new(), |mut acc, cell| {
                 acc.insert(cell.name.clone(), Cell::from_group(cell));
                 acc
@@ -219,15 +1340,21 @@ impl FromGroup for Library {
 impl ToGroup for Library {
     type Item = Library;
     fn into_group(self) -> Group {
-        let mut groups: Vec<Group> = Vec::with_capacity(self.groups.len() + self.cells.len());
-        groups.extend(self.cells.into_iter().map(|(_, cell)| cell.into_group()));
+        // Cells are stored in a map, so sort them for a reproducible order
+        let mut cells: Vec<Cell> = self.cells.into_values().collect();
+        cells.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut groups: Vec<Group> = Vec::with_capacity(self.groups.len() + cells.len());
+        groups.extend(cells.into_iter().map(|cell| cell.into_group()));
         groups.extend(self.groups);
         Group {
             name: self.name,
             type_: String::from("library"),
             simple_attributes: self.simple_attributes,
             complex_attributes: self.complex_attributes,
-            groups: groups,
+            comments: self.comments,
+            attribute_order: self.attribute_order,
+            unknown: self.unknown,
+            groups,
         }
     }
 }
@@ -240,7 +1367,10 @@ impl FromGroup for Cell {
             name: group.name,
             simple_attributes: group.simple_attributes,
             complex_attributes: group.complex_attributes,
-            groups: groups,
+            comments: group.comments,
+            attribute_order: group.attribute_order,
+            unknown: group.unknown,
+            groups,
             pins: pins.into_iter().fold(HashMap::new(), |mut acc, pin| {
                 acc.insert(pin.name.clone(), Pin::from_group(pin));
                 acc
@@ -253,15 +1383,20 @@ impl ToGroup for Cell {
     type Item = Cell;
 
     fn into_group(self) -> Group {
-        let mut groups: Vec<Group> = Vec::with_capacity(self.groups.len() + self.pins.len());
-        groups.extend(self.pins.into_iter().map(|(_, pin)| pin.into_group()));
+        let mut pins: Vec<Pin> = self.pins.into_values().collect();
+        pins.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut groups: Vec<Group> = Vec::with_capacity(self.groups.len() + pins.len());
+        groups.extend(pins.into_iter().map(|pin| pin.into_group()));
         groups.extend(self.groups);
         Group {
             name: self.name,
             type_: String::from("cell"),
             simple_attributes: self.simple_attributes,
             complex_attributes: self.complex_attributes,
-            groups: groups,
+            comments: self.comments,
+            attribute_order: self.attribute_order,
+            unknown: self.unknown,
+            groups,
         }
     }
 }
@@ -273,6 +1408,9 @@ impl FromGroup for Pin {
             name: group.name,
             simple_attributes: group.simple_attributes,
             complex_attributes: group.complex_attributes,
+            comments: group.comments,
+            attribute_order: group.attribute_order,
+            unknown: group.unknown,
             groups: group.groups,
         }
     }
@@ -286,6 +1424,9 @@ impl ToGroup for Pin {
             type_: String::from("pin"),
             simple_attributes: self.simple_attributes,
             complex_attributes: self.complex_attributes,
+            comments: self.comments,
+            attribute_order: self.attribute_order,
+            unknown: self.unknown,
             groups: self.groups,
         }
     }
@@ -303,6 +1444,731 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_iter_libraries_mut() {
+        let mut liberty = crate::parse_lib(
+            "library(fast) { revision : 1; }
+            library(slow) { revision : 1; }",
+        )
+        .unwrap();
+        for lib in liberty.iter_libraries_mut() {
+            lib.simple_attributes
+                .insert("revision".to_string(), Value::Float(2.0));
+        }
+        assert!(liberty
+            .iter_libraries()
+            .all(|lib| lib.simple_attributes["revision"] == Value::Float(2.0)));
+        liberty
+            .get_library_mut("slow")
+            .unwrap()
+            .simple_attributes
+            .insert("revision".to_string(), Value::Float(3.0));
+        assert_eq!(
+            liberty["slow"].simple_attributes["revision"],
+            Value::Float(3.0)
+        );
+        assert_eq!(liberty.iter_libraries().count(), 2);
+    }
+
+    #[test]
+    fn test_get_dotted_path() {
+        let liberty = crate::parse_lib(include_str!("../data/small.lib")).unwrap();
+        assert_eq!(liberty.get("foo.nom_voltage"), Some(&Value::Float(5.0)));
+        assert_eq!(
+            liberty.get("foo.operating_conditions[typical].temperature"),
+            Some(&Value::Float(25.0))
+        );
+        assert_eq!(liberty.get("foo.typical.process"), Some(&Value::Float(1.0)));
+        let cell = liberty["foo"].cells.values().next().unwrap();
+        let (attr, value) = cell.simple_attributes.iter().next().unwrap();
+        assert_eq!(
+            liberty.get(&format!("foo.{}.{}", cell.name, attr)),
+            Some(value)
+        );
+        assert_eq!(
+            liberty.get(&format!("foo.cell[{}].{}", cell.name, attr)),
+            Some(value)
+        );
+        assert_eq!(
+            liberty.get(&format!("foo.pin[{}].{}", cell.name, attr)),
+            None
+        );
+        assert_eq!(liberty.get("foo.operating_conditions[typical]"), None);
+        assert_eq!(liberty.get("foo.missing"), None);
+        assert_eq!(liberty.get("bar.nom_voltage"), None);
+        assert_eq!(liberty.get("foo"), None);
+        assert_eq!(liberty.get("foo.typical[.process"), None);
+    }
+
+    #[test]
+    fn test_remove_library() {
+        let mut liberty = crate::parse_lib(
+            "library(fast) { revision : 1; }
+            library(slow) { revision : 2; }",
+        )
+        .unwrap();
+        let removed = liberty.remove_library("fast").unwrap();
+        assert_eq!(removed.name, "fast");
+        assert!(liberty.remove_library("fast").is_none());
+        let names: Vec<&str> = liberty
+            .iter_libraries()
+            .map(|lib| lib.name.as_str())
+            .collect();
+        assert_eq!(names, ["slow"]);
+
+        liberty.0.push(removed);
+        liberty.retain_libraries(|lib| lib.simple_attributes["revision"] == Value::Float(1.0));
+        assert_eq!(liberty.0.len(), 1);
+        assert_eq!(liberty.0[0].name, "fast");
+    }
+
+    #[test]
+    fn test_rename_cell() {
+        let mut liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) { area : 1; }
+                cell(AND2X1) { area : 2; }
+                cell(wrapper) {
+                    pin(o) {
+                        timing() { related_pin : "AND2"; }
+                        function : "AND2X1 & !AND2";
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(liberty.rename_cell("AND2", "AND2_X2"));
+        let lib = &liberty["foo"];
+        assert!(!lib.cells.contains_key("AND2"));
+        assert_eq!(lib.cells["AND2_X2"].name, "AND2_X2");
+        assert!(lib.cells.contains_key("AND2X1"));
+        let pin = &lib.cells["wrapper"].pins["o"];
+        assert_eq!(
+            pin.groups[0].simple_attributes["related_pin"],
+            Value::String("AND2_X2".to_string())
+        );
+        assert_eq!(
+            pin.simple_attributes["function"],
+            Value::String("AND2X1 & !AND2_X2".to_string())
+        );
+        assert!(!liberty.rename_cell("missing", "other"));
+        assert!(!liberty.rename_cell("AND2X1", "AND2_X2"));
+    }
+
+    #[test]
+    fn test_pin_counts() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) {
+                    pin(a) { direction : input; }
+                    pin(b) { direction : "input"; }
+                    pin(o) { direction : output; }
+                    pin(n) { direction : internal; }
+                }
+            }"#,
+        )
+        .unwrap();
+        let cell = liberty["foo"].cells["AND2"].clone().into_group();
+        assert_eq!(cell.pin_counts(), (2, 1, 0));
+        assert_eq!(cell.iter_pins().count(), 4);
+        assert_eq!(
+            cell.iter_pins()
+                .find(|p| p.name == "n")
+                .and_then(Group::pin_direction),
+            Some(PinDirection::Internal)
+        );
+    }
+
+    #[test]
+    fn test_date_revision() {
+        let liberty = crate::parse_lib(
+            r#"library(quoted) { date : "Mon Jan 1 2024"; revision : "1.2a"; }
+            library(bare) { revision : 1.2; }
+            library(integer) { revision : 3; }"#,
+        )
+        .unwrap();
+        let quoted = liberty["quoted"].clone().into_group();
+        assert_eq!(quoted.date(), Some("Mon Jan 1 2024"));
+        assert_eq!(quoted.revision().as_deref(), Some("1.2a"));
+        assert_eq!(liberty["bare"].revision().as_deref(), Some("1.2"));
+        assert_eq!(liberty["bare"].date(), None);
+        assert_eq!(liberty["integer"].revision().as_deref(), Some("3"));
+        assert_eq!(Group::new("library", "x").revision(), None);
+    }
+
+    #[test]
+    fn test_technology() {
+        let liberty = crate::parse_lib(
+            r#"library(tight) { technology(cmos); }
+            library(spaced) { technology ( fpga ) ; }
+            library(quoted) { technology ("cmos"); }
+            library(none) { delay_model : table_lookup; }"#,
+        )
+        .unwrap();
+        assert_eq!(liberty["tight"].technology(), Some("cmos"));
+        assert_eq!(liberty["spaced"].technology(), Some("fpga"));
+        assert_eq!(liberty["quoted"].technology(), Some("cmos"));
+        assert_eq!(liberty["none"].technology(), None);
+        assert_eq!(
+            liberty["tight"].clone().into_group().technology(),
+            Some("cmos")
+        );
+        assert_eq!(liberty["none"].clone().into_group().technology(), None);
+    }
+
+    #[test]
+    fn test_iter_attributes() {
+        let lib = crate::parse_lib(
+            "library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    pin_opposite (a, b);
+                    cell_footprint : and2;
+                }
+            }",
+        )
+        .unwrap();
+        let mut cell = lib["foo"].cells["AND2"].clone().into_group();
+        let names: Vec<&str> = cell
+            .iter_attributes()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["area", "pin_opposite", "cell_footprint"]);
+        assert_eq!(
+            cell.iter_attributes().next(),
+            Some((&"area".to_string(), Attribute::Simple(&Value::Float(1.0))))
+        );
+        let attributes: Vec<_> = cell.iter_attributes().map(|(_, attr)| attr).collect();
+        assert_eq!(attributes[0].as_simple(), Some(&Value::Float(1.0)));
+        assert_eq!(attributes[0].as_complex(), None);
+        let pins = [
+            Value::Expression("a".to_string()),
+            Value::Expression("b".to_string()),
+        ];
+        assert_eq!(attributes[1].as_complex(), Some(&pins[..]));
+        assert_eq!(attributes[1].as_simple(), None);
+
+        for (_, attribute) in cell.iter_attributes_mut() {
+            match attribute {
+                AttributeMut::Simple(value) => *value = Value::Bool(true),
+                AttributeMut::Complex(values) => values.clear(),
+            }
+        }
+        assert_eq!(cell["area"], Value::Bool(true));
+        assert!(cell.complex_attributes["pin_opposite"].is_empty());
+
+        // Attributes inserted directly come last, and the order is kept when written
+        let mut lib = lib;
+        let and2 = lib.0[0].cells.get_mut("AND2").unwrap();
+        and2.simple_attributes
+            .insert("dont_use".to_string(), Value::Bool(true));
+        let output = lib.to_string();
+        let position = |name: &str| output.find(name).unwrap();
+        assert!(position("area") < position("pin_opposite"));
+        assert!(position("pin_opposite") < position("cell_footprint"));
+        assert!(position("cell_footprint") < position("dont_use"));
+        assert_eq!(output, lib.clone().to_ast().to_string());
+    }
+
+    #[test]
+    fn test_insert_attribute_after() {
+        let mut liberty = crate::parse_lib(
+            "library(foo) {
+                operating_conditions(typ) {
+                    process : 1;
+                    temperature : 25;
+                }
+            }",
+        )
+        .unwrap();
+        let conditions = &mut liberty.0[0].groups[0];
+        let voltage = AttributeValue::Simple(Value::Float(1.1));
+        assert!(conditions.insert_attribute_after("process", "voltage", voltage.clone()));
+        assert!(!conditions.insert_attribute_after("missing", "voltage", voltage));
+        let tree = AttributeValue::Complex(vec![Value::Expression("balanced".to_string())]);
+        assert!(conditions.insert_attribute_after("temperature", "tree_type", tree));
+        let names: Vec<&str> = conditions
+            .iter_attributes()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["process", "voltage", "temperature", "tree_type"]);
+
+        let output = liberty.to_string();
+        let position = |name: &str| output.find(name).unwrap();
+        assert!(position("process") < position("voltage"));
+        assert!(position("voltage") < position("temperature"));
+        assert!(position("temperature") < position("tree_type"));
+        let reparsed = crate::parse_lib(&output).unwrap();
+        assert_eq!(reparsed, liberty);
+        assert_eq!(reparsed.to_string(), output);
+    }
+
+    #[test]
+    fn test_clone_filtered() {
+        let lib = crate::parse_lib(
+            "library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    cell_leakage_power : 0.5;
+                    pin(A) { direction : input; capacitance : 0.1; }
+                    leakage_power() { value : 0.5; }
+                }
+            }",
+        )
+        .unwrap();
+        let cell = lib.0[0].cells["AND2"].clone().into_group();
+        let reduced = cell.clone_filtered(&["area"], &["pin"]);
+        assert_eq!(reduced.name, "AND2");
+        assert_eq!(reduced.simple_attributes.len(), 1);
+        assert_eq!(reduced.simple_attribute("area"), Some(&Value::Float(1.0)));
+        assert_eq!(reduced.groups.len(), 1);
+        assert_eq!(reduced.groups[0].type_, "pin");
+        assert_eq!(reduced.groups[0].name, "A");
+        assert_eq!(reduced.groups[0].attribute_count(), 0);
+    }
+
+    #[test]
+    fn test_sort_subgroups_by_name() {
+        let mut group = Group::new("library", "foo");
+        for (type_, name) in &[
+            ("cell", "B"),
+            ("timing", "t2"),
+            ("cell", "A"),
+            ("timing", "t1"),
+        ] {
+            group.groups.push(Group::new(type_, name));
+        }
+        group.groups[0]
+            .simple_attributes
+            .insert("area".to_string(), Value::Float(1.0));
+        group.sort_subgroups_by_name();
+        let order: Vec<(&str, &str)> = group
+            .groups
+            .iter()
+            .map(|g| (g.type_.as_str(), g.name.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("cell", "A"),
+                ("timing", "t1"),
+                ("cell", "B"),
+                ("timing", "t2")
+            ]
+        );
+        assert_eq!(group.groups[2].simple_attributes.len(), 1);
+    }
+
+    #[test]
+    fn test_sort_cells() {
+        let mut liberty = crate::parse_lib(
+            "library(foo) {
+                cell(NOR2) { area : 1; }
+                cell(AND2) { area : 1; }
+                cell(INV) { area : 1; }
+                lu_table_template(b) { variable_1 : input_net_transition; }
+                lu_table_template(a) { variable_1 : input_net_transition; }
+            }",
+        )
+        .unwrap();
+        liberty.sort_cells();
+        let output = liberty.to_string();
+        let position = |name: &str| output.find(name).unwrap();
+        assert!(position("AND2") < position("INV"));
+        assert!(position("INV") < position("NOR2"));
+        assert!(position("(a)") < position("(b)"));
+        assert_eq!(output, liberty.clone().to_ast().to_string());
+    }
+
+    #[test]
+    fn test_from_str() {
+        let liberty: Liberty = "library(foo) { cell(AND2) {} }".parse().unwrap();
+        assert_eq!(liberty.cell_count(), 1);
+        assert!("library(foo) { cell(AND2) }".parse::<Liberty>().is_err());
+    }
+
+    #[test]
+    fn test_unknown_statements() {
+        let liberty = crate::parse_lib(
+            "library(foo) { foo_bar_statement xyz; cell(AND2) { area : 1; new_block { a b; } } }",
+        )
+        .unwrap();
+        assert_eq!(liberty["foo"].unknown, ["foo_bar_statement xyz;"]);
+        assert_eq!(liberty["foo"].cells["AND2"].unknown, ["new_block { a b; }"]);
+        let output = liberty.to_string();
+        assert!(output.contains("foo_bar_statement xyz;"));
+        assert_eq!(output, liberty.clone().to_ast().to_string());
+        assert_eq!(crate::parse_lib(&output).unwrap(), liberty);
+
+        // Well-known attribute names aren't taken as unknown statements
+        assert!(crate::parse_lib("library(foo) { cell(AND2) { area 1; } }").is_err());
+    }
+
+    #[test]
+    fn test_library_features() {
+        let lib = crate::parse_lib(
+            "library(foo) {
+                library_features(report_delay_calculation, report_power_calculation);
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            lib.library_features(),
+            vec!["report_delay_calculation", "report_power_calculation"]
+        );
+    }
+
+    #[test]
+    fn test_normalize_whitespace_in_expressions() {
+        let mut liberty = crate::parse_lib(
+            r#"library(foo) {
+                v : A   +  B;
+                cell(AND2) {
+                    s : "A   +  B";
+                    pin(o) { w : B *
+                        C; }
+                    pair (A  *  B, "x  y");
+                }
+            }"#,
+        )
+        .unwrap();
+        liberty.normalize_whitespace_in_expressions();
+        let lib = &liberty["foo"];
+        assert_eq!(lib.simple_attributes["v"], Value::expression("A + B"));
+        let cell = &lib.cells["AND2"];
+        assert_eq!(cell.simple_attributes["s"], Value::quoted("A   +  B"));
+        assert_eq!(
+            cell.pins["o"].simple_attributes["w"],
+            Value::expression("B * C")
+        );
+        assert_eq!(
+            cell.complex_attributes["pair"],
+            vec![Value::expression("A * B"), Value::quoted("x  y")]
+        );
+
+        let mut group = Group::new("timing", "");
+        group
+            .simple_attributes
+            .insert("when".to_string(), Value::expression(" A  + B "));
+        group.normalize_whitespace_in_expressions();
+        assert_eq!(group.simple_attributes["when"], Value::expression("A + B"));
+    }
+
+    #[test]
+    fn test_rewrite_attribute() {
+        let mut lib = crate::parse_lib(
+            "library(foo) {
+                area : 10;
+                cell(AND2) {
+                    area : 1;
+                    pin(a) { area : 2; capacitance : 0.1; }
+                }
+                cell(INV) {
+                    area : 3;
+                    pin_opposite(a, y);
+                }
+            }",
+        )
+        .unwrap();
+        lib.rewrite_attribute("area", |v| *v = Value::Float(v.float() * 2.0));
+        lib.rewrite_complex_attribute("pin_opposite", |values| values.reverse());
+        let foo = &lib["foo"];
+        assert_eq!(foo.simple_attributes["area"], Value::Float(20.0));
+        assert_eq!(
+            foo.cells["AND2"].simple_attributes["area"],
+            Value::Float(2.0)
+        );
+        assert_eq!(
+            foo.cells["INV"].simple_attributes["area"],
+            Value::Float(6.0)
+        );
+        let pin = &foo.cells["AND2"].pins["a"];
+        assert_eq!(pin.simple_attributes["area"], Value::Float(4.0));
+        assert_eq!(pin.simple_attributes["capacitance"], Value::Float(0.1));
+        assert_eq!(
+            foo.cells["INV"].complex_attributes["pin_opposite"],
+            vec![
+                Value::Expression("y".to_string()),
+                Value::Expression("a".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let liberty = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        assert_eq!(
+            liberty.summary(),
+            LibrarySummary {
+                library_count: 1,
+                cell_count: 5,
+                sequential_cell_count: 1,
+                combinational_cell_count: 4,
+                pin_count: 16,
+                timing_arc_count: 0,
+                template_count: 0,
+                operating_conditions: vec![],
+            }
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_summary_serialize() {
+        let liberty = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        let json = serde_json::to_value(liberty.summary()).unwrap();
+        assert_eq!(json["cell_count"], 5);
+        assert_eq!(json["sequential_cell_count"], 1);
+    }
+
+    #[test]
+    fn test_extract_path() {
+        let liberty = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let lib_name = liberty.0[0].name.clone();
+        let pin = liberty
+            .extract_path(&lib_name, &["cell:AND2", "pin:o"])
+            .unwrap();
+        assert_eq!((pin.type_.as_str(), pin.name.as_str()), ("pin", "o"));
+        assert_eq!(
+            pin,
+            liberty[lib_name.as_str()].cells["AND2"].pins["o"]
+                .clone()
+                .into_group()
+        );
+        let rise = liberty
+            .extract_path(&lib_name, &["cell:AND2", "pin:o", "timing", "cell_rise"])
+            .unwrap();
+        assert_eq!(
+            pin.find_subgroup_path(&["timing", "cell_rise"]),
+            Some(&rise)
+        );
+        assert!(liberty
+            .extract_path(&lib_name, &["cell:AND2", "pin:missing"])
+            .is_none());
+        assert!(liberty.extract_path("missing", &[]).is_none());
+    }
+
+    #[test]
+    fn test_extract_cell() {
+        let liberty = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let lib_name = liberty.0[0].name.clone();
+        let and2 = liberty.extract_cell(&lib_name, "AND2").unwrap();
+        assert_eq!((and2.type_.as_str(), and2.name.as_str()), ("cell", "AND2"));
+        assert!(liberty.extract_cell(&lib_name, "missing").is_none());
+        assert!(liberty.extract_cell("missing", "AND2").is_none());
+
+        let mut bar = Group::new("library", "bar");
+        bar.groups.push(and2);
+        let bar = Library::from_group(bar);
+        assert_eq!(bar.cells["AND2"], liberty[lib_name.as_str()].cells["AND2"]);
+    }
+
+    #[test]
+    fn test_bus_pin() {
+        let pins: String = (0..8)
+            .map(|i| format!("pin(A[{}]) {{ capacitance : 0.{}; }}\n", i, i))
+            .collect();
+        let text = format!(
+            "library(foo) {{ cell(REG8) {{ bus(A) {{ bus_type : bus8; {} }} }} }}",
+            pins
+        );
+        let liberty = crate::parse_lib(&text).unwrap();
+        let cell = liberty["foo"].cells["REG8"].clone().into_group();
+        let pin = cell.bus_pin("A", 3).unwrap();
+        assert_eq!(pin.name, "A[3]");
+        assert_eq!(
+            pin.simple_attribute("capacitance"),
+            Some(&Value::Float(0.3))
+        );
+        assert!(cell.bus_pin("A", 8).is_none());
+        assert!(cell.bus_pin("B", 3).is_none());
+    }
+
+    #[test]
+    fn test_drop_default_equal_attributes() {
+        let mut liberty = crate::parse_lib(
+            "library(foo) {
+                default_fanout_load : 1;
+                default_cell_leakage_power : 0.5;
+                cell(AND2) {
+                    cell_leakage_power : 0.5;
+                    pin(a) { fanout_load : 1; direction : input; }
+                    pin(b) { fanout_load : 2; }
+                }
+            }",
+        )
+        .unwrap();
+        liberty.drop_default_equal_attributes();
+        let and2 = &liberty["foo"].cells["AND2"];
+        assert!(and2.simple_attributes.is_empty());
+        assert!(!and2.pins["a"].simple_attributes.contains_key("fanout_load"));
+        assert!(and2.pins["a"].simple_attributes.contains_key("direction"));
+        assert_eq!(
+            and2.pins["b"].simple_attributes["fanout_load"],
+            Value::Float(2.0)
+        );
+        assert_eq!(
+            liberty["foo"].simple_attributes["default_fanout_load"],
+            Value::Float(1.0)
+        );
+    }
+
+    #[test]
+    fn test_group_counts() {
+        let liberty = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        assert_eq!(liberty.cell_count(), 5);
+        let lib = liberty["cells"].clone();
+        let and2 = lib.cells["AND2"].clone().into_group();
+        assert_eq!(and2.attribute_count(), 1);
+        assert_eq!(and2.subgroup_count(), 3);
+        assert_eq!(and2.total_group_count(), 3);
+        let group = lib.into_group();
+        assert_eq!(group.attribute_count(), 0);
+        assert_eq!(group.subgroup_count(), 5);
+        // 5 cells, 16 pins, and the DFF's `ff` group
+        assert_eq!(group.total_group_count(), 22);
+    }
+
+    #[test]
+    fn test_remove_subgroup() {
+        let mut group = Group::new("cell", "AND2");
+        for name in &["a", "b", "o"] {
+            group.groups.push(Group::new("pin", name));
+        }
+        group.groups.push(Group::new("leakage_power", "b"));
+        assert_eq!(group.remove_subgroup("pin", "c"), None);
+        assert_eq!(
+            group.remove_subgroup("pin", "b"),
+            Some(Group::new("pin", "b"))
+        );
+        let names: Vec<_> = group.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "o", "b"]);
+        assert_eq!(group.remove_subgroup_at(3), None);
+        assert_eq!(group.remove_subgroup_at(0), Some(Group::new("pin", "a")));
+        assert_eq!(group.groups[0].name, "o");
+    }
+
+    #[test]
+    fn test_group_lookup_ci() {
+        let mut group = Group::new("cell", "AND2");
+        group
+            .simple_attributes
+            .insert("Area".to_string(), Value::Float(1.0));
+        group
+            .complex_attributes
+            .insert("Pg_Pin".to_string(), vec![Value::Float(2.0)]);
+        group.groups.push(Group::new("PIN", "a"));
+        group.groups.push(Group::new("pin", "b"));
+        assert_eq!(group.simple_attribute("area"), None);
+        assert_eq!(group.simple_attribute_ci("area"), Some(&Value::Float(1.0)));
+        assert_eq!(group.complex_attribute("pg_pin"), None);
+        assert_eq!(
+            group.complex_attribute_ci("pg_pin"),
+            Some(&vec![Value::Float(2.0)])
+        );
+        assert_eq!(group.iter_subgroups_of_type("pin").count(), 1);
+        assert_eq!(group.iter_subgroups_of_type_ci("pin").count(), 2);
+    }
+
+    #[test]
+    fn test_iter_descendants_of_type() {
+        let liberty = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let cell = liberty.0[0].cells["AND2"].clone();
+        let expected: usize = cell
+            .pins
+            .values()
+            .flat_map(|pin| pin.groups.iter().filter(|g| g.type_ == "timing"))
+            .map(|timing| timing.iter_subgroups_of_type("cell_rise").count())
+            .sum();
+        assert!(expected > 1);
+        let cell = cell.into_group();
+        assert_eq!(cell.iter_subgroups_of_type("cell_rise").count(), 0);
+        assert_eq!(cell.iter_descendants_of_type("cell_rise").count(), expected);
+        assert!(cell
+            .iter_descendants_of_type("cell_rise")
+            .all(|g| g.type_ == "cell_rise"));
+        assert_eq!(cell.iter_descendants_of_type("missing").count(), 0);
+
+        let mut outer = Group::new("g", "outer");
+        let mut inner = Group::new("g", "inner");
+        inner.groups.push(Group::new("g", "innermost"));
+        outer.groups.push(inner);
+        outer.groups.push(Group::new("g", "sibling"));
+        let mut root = Group::new("root", "");
+        root.groups.push(outer);
+        let names: Vec<&str> = root
+            .iter_descendants_of_type("g")
+            .map(|g| g.name.as_str())
+            .collect();
+        assert_eq!(names, ["outer", "inner", "innermost", "sibling"]);
+    }
+
+    #[test]
+    fn test_pin_accessors() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(X) {
+                    pin(a) { direction : input; capacitance : 0.5; }
+                    pin(b) { direction : "inout"; }
+                    pin(i) { direction : internal; }
+                    pin(o) { direction : output; function : "(a & b)'"; }
+                    pin(u) { direction : sideways; }
+                    pin(n) { }
+                }
+            }"#,
+        )
+        .unwrap();
+        let pins = &liberty["foo"].cells["X"].pins;
+        let directions: Vec<Option<PinDirection>> = ["a", "b", "i", "o", "u", "n"]
+            .iter()
+            .map(|name| pins[*name].pin_direction())
+            .collect();
+        assert_eq!(
+            directions,
+            [
+                Some(PinDirection::Input),
+                Some(PinDirection::Inout),
+                Some(PinDirection::Internal),
+                Some(PinDirection::Output),
+                None,
+                None
+            ]
+        );
+        assert_eq!(pins["a"].capacitance(), Some(0.5));
+        assert_eq!(pins["o"].capacitance(), None);
+        assert_eq!(pins["o"].function(), Some("(a & b)'"));
+        assert_eq!(pins["a"].function(), None);
+
+        let group = pins["o"].clone().into_group();
+        assert_eq!(group.pin_direction(), Some(PinDirection::Output));
+        assert_eq!(group.function(), Some("(a & b)'"));
+        assert_eq!(group.capacitance(), None);
+        let group = pins["a"].clone().into_group();
+        assert_eq!(group.capacitance(), Some(0.5));
+        assert_eq!(Group::new("pin", "n").pin_direction(), None);
+    }
+
+    #[test]
+    fn test_pin_clock_flags() {
+        let lib = crate::parse_lib(
+            "library(foo) {
+                cell(ICG) {
+                    pin(CK) { clock : true; clock_gate_clock_pin : true; }
+                    pin(EN) { clock_gate_enable_pin : \"true\"; }
+                    pin(Q) { clock : false; }
+                }
+            }",
+        )
+        .unwrap();
+        let pins = &lib["foo"].cells["ICG"].pins;
+        assert!(pins["CK"].is_clock());
+        assert!(!pins["EN"].is_clock());
+        assert!(!pins["Q"].is_clock());
+        assert_eq!(pins["CK"].clock_gate_role(), Some(ClockGateRole::Clock));
+        assert_eq!(pins["EN"].clock_gate_role(), Some(ClockGateRole::Enable));
+        assert_eq!(pins["Q"].clock_gate_role(), None);
+    }
+
     #[test]
     fn test_pin_into_group() {
         let mut pin = Pin::new("my_pin");
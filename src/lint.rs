@@ -0,0 +1,230 @@
+//! Opt-in checks for attributes that are misspelled or placed in the wrong group
+//!
+//! The parser accepts any attribute name in any group, so a typo such as `aera : 1;` parses
+//! fine. [`lint`] checks each attribute against a built-in table of well-known attributes and
+//! the group types they belong to, taken from the Liberty reference manual.
+
+use crate::{
+    ast::Value,
+    liberty::Liberty,
+    view::{GroupView, ViewVisitor},
+    visit::GroupPath,
+};
+
+/// No group types, meaning the attribute may appear in any group, such as table `values`
+const ANY_GROUP: &[&str] = &[];
+
+const LIBRARY: &[&str] = &["library"];
+const CELL: &[&str] = &["cell"];
+const PIN: &[&str] = &["pin", "bus", "bundle"];
+const TIMING: &[&str] = &["timing"];
+const POWER: &[&str] = &["timing", "internal_power"];
+const FF: &[&str] = &["ff", "ff_bank"];
+const LATCH: &[&str] = &["latch", "latch_bank"];
+const SEQUENTIAL: &[&str] = &["ff", "ff_bank", "latch", "latch_bank"];
+const CONDITIONAL: &[&str] = &["timing", "internal_power", "leakage_power"];
+const OPERATING_CONDITIONS: &[&str] = &["operating_conditions"];
+
+/// Well-known attributes and the group types they belong to
+pub(crate) const KNOWN_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("area", CELL),
+    ("capacitance", PIN),
+    ("capacitive_load_unit", LIBRARY),
+    ("cell_footprint", CELL),
+    ("cell_leakage_power", CELL),
+    ("clear", SEQUENTIAL),
+    ("clock", PIN),
+    ("clocked_on", FF),
+    ("comment", LIBRARY),
+    ("current_unit", LIBRARY),
+    ("data_in", LATCH),
+    ("date", LIBRARY),
+    ("default_operating_conditions", LIBRARY),
+    ("delay_model", LIBRARY),
+    ("direction", PIN),
+    ("dont_touch", CELL),
+    ("dont_use", CELL),
+    ("enable", LATCH),
+    ("fall_capacitance", PIN),
+    ("fall_capacitance_range", PIN),
+    ("fanout_load", PIN),
+    ("function", PIN),
+    ("index_1", ANY_GROUP),
+    ("index_2", ANY_GROUP),
+    ("index_3", ANY_GROUP),
+    ("input_threshold_pct_fall", LIBRARY),
+    ("input_threshold_pct_rise", LIBRARY),
+    ("leakage_power_unit", LIBRARY),
+    ("library_features", LIBRARY),
+    ("max_capacitance", PIN),
+    ("max_fanout", PIN),
+    ("max_transition", PIN),
+    ("next_state", FF),
+    ("nom_process", LIBRARY),
+    ("nom_temperature", LIBRARY),
+    ("nom_voltage", LIBRARY),
+    ("output_threshold_pct_fall", LIBRARY),
+    ("output_threshold_pct_rise", LIBRARY),
+    ("process", OPERATING_CONDITIONS),
+    ("pulling_resistance_unit", LIBRARY),
+    ("related_ground_pin", PIN),
+    ("related_pin", POWER),
+    ("related_power_pin", PIN),
+    ("revision", LIBRARY),
+    ("rise_capacitance", PIN),
+    ("rise_capacitance_range", PIN),
+    ("sdf_cond", TIMING),
+    ("slew_lower_threshold_pct_fall", LIBRARY),
+    ("slew_lower_threshold_pct_rise", LIBRARY),
+    ("slew_upper_threshold_pct_fall", LIBRARY),
+    ("slew_upper_threshold_pct_rise", LIBRARY),
+    ("temperature", OPERATING_CONDITIONS),
+    ("time_unit", LIBRARY),
+    ("timing_sense", TIMING),
+    ("timing_type", TIMING),
+    ("values", ANY_GROUP),
+    ("variable_1", ANY_GROUP),
+    ("variable_2", ANY_GROUP),
+    ("variable_3", ANY_GROUP),
+    ("voltage", OPERATING_CONDITIONS),
+    ("voltage_unit", LIBRARY),
+    ("when", CONDITIONAL),
+];
+
+/// How likely a [`Lint`] is to be a real problem
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Severity {
+    /// The attribute isn't in the built-in table, which may just mean it's uncommon
+    Info,
+    /// A well-known attribute is in a group type it doesn't belong to
+    Warning,
+}
+
+/// Issue found by [`lint`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lint {
+    /// Path to the group containing the attribute, such as `library(foo)/cell(AND2)`
+    pub path: String,
+    pub message: String,
+    pub severity: Severity,
+}
+
+/// Check every attribute against the built-in table of well-known attributes
+///
+/// Lints are sorted by path.
+pub fn lint(liberty: &Liberty) -> Vec<Lint> {
+    let mut linter = Linter::default();
+    for lib in &liberty.0 {
+        GroupView::Library(lib).visit(&mut linter);
+    }
+    linter.lints.sort_by(|a, b| a.path.cmp(&b.path));
+    linter.lints
+}
+
+#[derive(Default)]
+struct Linter {
+    path: GroupPath,
+    lints: Vec<Lint>,
+}
+
+impl Linter {
+    fn check(&mut self, name: &str) {
+        let (type_, path) = match self.path.group_type() {
+            Some(type_) => (type_, self.path.path()),
+            None => return,
+        };
+        let lint = match KNOWN_ATTRIBUTES.iter().find(|(known, _)| *known == name) {
+            None => Lint {
+                path: path.to_string(),
+                message: format!("Unknown attribute '{}'", name),
+                severity: Severity::Info,
+            },
+            Some((_, groups)) if groups.is_empty() || groups.contains(&type_) => return,
+            Some((_, groups)) => Lint {
+                path: path.to_string(),
+                message: format!(
+                    "Attribute '{}' belongs on {} groups, not '{}'",
+                    name,
+                    groups.join("/"),
+                    type_
+                ),
+                severity: Severity::Warning,
+            },
+        };
+        self.lints.push(lint);
+    }
+}
+
+impl ViewVisitor<'_> for Linter {
+    fn enter_group(&mut self, group: &GroupView<'_>) {
+        self.path.enter(group);
+    }
+
+    fn leave_group(&mut self, _group: &GroupView<'_>) {
+        self.path.leave();
+    }
+
+    fn simple_attr(&mut self, name: &str, _value: &Value) {
+        self.check(name);
+    }
+
+    fn complex_attr(&mut self, name: &str, _values: &[Value]) {
+        self.check(name);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                delay_model : table_lookup;
+                capacitive_load_unit (1, pf);
+                cell(AND2) {
+                    area : 1;
+                    direction : output;
+                    aera : 2;
+                    pin(o) {
+                        direction : output;
+                        timing() {
+                            related_pin : "a";
+                            cell_rise(delay_template) { values ("1"); }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            lint(&liberty),
+            vec![
+                Lint {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    message: "Unknown attribute 'aera'".to_string(),
+                    severity: Severity::Info,
+                },
+                Lint {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    message: "Attribute 'direction' belongs on pin/bus/bundle groups, not 'cell'"
+                        .to_string(),
+                    severity: Severity::Warning,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_clean() {
+        for contents in [
+            include_str!("../data/cells.lib"),
+            include_str!("../data/cells_timing.lib"),
+            include_str!("../data/small.lib"),
+        ] {
+            let liberty = crate::parse_lib(contents).unwrap();
+            assert_eq!(lint(&liberty), vec![]);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! Merging groups and libraries, such as overlaying one corner library onto another
+
+use std::{collections::HashMap, error, fmt};
+
+use crate::liberty::{FromGroup, Group, Liberty, Library, ToGroup};
+
+/// How [`Group::merge`] resolves an attribute set in both groups
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MergeStrategy {
+    /// Replace the existing value with the other group's value
+    Overwrite,
+    /// Keep the existing value
+    KeepExisting,
+    /// Fail with a [`MergeConflict`]
+    Error,
+}
+
+/// Attribute set in both groups of a merge using [`MergeStrategy::Error`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Path to the attribute from the merged group, such as `cell(AND2)/area`
+    pub path: String,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conflicting attribute '{}'", self.path)
+    }
+}
+
+impl error::Error for MergeConflict {}
+
+impl Group {
+    /// Merge `other` into this group
+    ///
+    /// Attributes only in `other` are added, and those in both are resolved by `strategy`.
+    /// Subgroups with the same type and name are merged recursively, and the remaining subgroups
+    /// of `other` are appended. Repeated pairs, such as several `timing()` groups in a pin, are
+    /// matched in order, and conflicts in them get an index suffix after the first as in
+    /// [`diff`](crate::diff), such as `timing()[1]`. On a conflict this group is left unchanged.
+    pub fn merge(&mut self, other: Group, strategy: MergeStrategy) -> Result<(), MergeConflict> {
+        let mut merged = self.clone();
+        merged.merge_at("", other, strategy)?;
+        *self = merged;
+        Ok(())
+    }
+
+    /// Remove the first sub-group matching `type_` and `name`, moving its contents into this group
+    ///
+    /// Its attributes are merged into this group's, resolving collisions by `strategy`. With
+    /// `prefix`, each attribute name is prefixed with the sub-group's name and `_` first. Its
+    /// sub-groups take its place. Returns `Ok(false)` if no sub-group matches. On a conflict
+    /// this group is left unchanged.
+    pub fn flatten_subgroup(
+        &mut self,
+        type_: &str,
+        name: &str,
+        strategy: MergeStrategy,
+        prefix: bool,
+    ) -> Result<bool, MergeConflict> {
+        let idx = match self
+            .groups
+            .iter()
+            .position(|g| g.type_ == type_ && g.name == name)
+        {
+            Some(idx) => idx,
+            None => return Ok(false),
+        };
+        let mut flattened = self.clone();
+        let subgroup = flattened.groups.remove(idx);
+        let rename = |attr: String| {
+            if prefix {
+                format!("{}_{}", name, attr)
+            } else {
+                attr
+            }
+        };
+        merge_attributes(
+            "",
+            &mut flattened.simple_attributes,
+            subgroup
+                .simple_attributes
+                .into_iter()
+                .map(|(k, v)| (rename(k), v))
+                .collect(),
+            strategy,
+        )?;
+        merge_attributes(
+            "",
+            &mut flattened.complex_attributes,
+            subgroup
+                .complex_attributes
+                .into_iter()
+                .map(|(k, v)| (rename(k), v))
+                .collect(),
+            strategy,
+        )?;
+        for name in subgroup.attribute_order.0 {
+            let name = rename(name);
+            if !flattened.attribute_order.0.contains(&name) {
+                flattened.attribute_order.0.push(name);
+            }
+        }
+        flattened.groups.splice(idx..idx, subgroup.groups);
+        *self = flattened;
+        Ok(true)
+    }
+
+    fn merge_at(
+        &mut self,
+        path: &str,
+        other: Group,
+        strategy: MergeStrategy,
+    ) -> Result<(), MergeConflict> {
+        merge_attributes(
+            path,
+            &mut self.simple_attributes,
+            other.simple_attributes,
+            strategy,
+        )?;
+        merge_attributes(
+            path,
+            &mut self.complex_attributes,
+            other.complex_attributes,
+            strategy,
+        )?;
+        for name in other.attribute_order.0 {
+            if !self.attribute_order.0.contains(&name) {
+                self.attribute_order.0.push(name);
+            }
+        }
+        for statement in other.unknown {
+            if !self.unknown.contains(&statement) {
+                self.unknown.push(statement);
+            }
+        }
+        let mut seen: HashMap<(String, String), usize> = HashMap::new();
+        for group in other.groups {
+            let count = seen
+                .entry((group.type_.clone(), group.name.clone()))
+                .or_insert(0);
+            let occurrence = *count;
+            *count += 1;
+            let existing = self
+                .groups
+                .iter_mut()
+                .filter(|g| g.type_ == group.type_ && g.name == group.name)
+                .nth(occurrence);
+            match existing {
+                Some(existing) => {
+                    let path = match occurrence {
+                        0 => format!("{}{}({})/", path, group.type_, group.name),
+                        n => format!("{}{}({})[{}]/", path, group.type_, group.name, n),
+                    };
+                    existing.merge_at(&path, group, strategy)?;
+                }
+                None => self.groups.push(group),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn merge_attributes<T>(
+    path: &str,
+    existing: &mut HashMap<String, T>,
+    other: HashMap<String, T>,
+    strategy: MergeStrategy,
+) -> Result<(), MergeConflict> {
+    for (name, value) in other {
+        match (existing.contains_key(&name), strategy) {
+            (false, _) | (true, MergeStrategy::Overwrite) => {
+                existing.insert(name, value);
+            }
+            (true, MergeStrategy::KeepExisting) => {}
+            (true, MergeStrategy::Error) => {
+                return Err(MergeConflict {
+                    path: format!("{}{}", path, name),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Liberty {
+    /// Merge the libraries of `other` into the libraries of the same name
+    ///
+    /// Libraries are merged with [`Group::merge`], and those without a match are appended. On a
+    /// conflict no library is changed.
+    pub fn merge(&mut self, other: Liberty, strategy: MergeStrategy) -> Result<(), MergeConflict> {
+        let mut merged = self.0.clone();
+        for lib in other.0 {
+            match merged.iter_mut().find(|l| l.name == lib.name) {
+                Some(existing) => {
+                    let mut group = existing.clone().into_group();
+                    group.merge_at("", lib.into_group(), strategy)?;
+                    *existing = Library::from_group(group);
+                }
+                None => merged.push(lib),
+            }
+        }
+        self.0 = merged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::Value;
+
+    fn corners() -> (Liberty, Liberty) {
+        let slow = crate::parse_lib(
+            "library(lib) {
+                cell(AND2) { area : 1; cell_footprint : and; }
+                cell(INV) { area : 2; }
+            }",
+        )
+        .unwrap();
+        let fast = crate::parse_lib(
+            "library(lib) {
+                cell(AND2) { area : 3; dont_use : true; }
+                cell(BUF) { area : 4; }
+            }",
+        )
+        .unwrap();
+        (slow, fast)
+    }
+
+    #[test]
+    fn test_merge_overwrite() {
+        let (mut slow, fast) = corners();
+        slow.merge(fast, MergeStrategy::Overwrite).unwrap();
+        let lib = &slow["lib"];
+        assert_eq!(lib.cells.len(), 3);
+        let and2 = &lib.cells["AND2"].simple_attributes;
+        assert_eq!(and2["area"], Value::Float(3.0));
+        assert_eq!(and2["dont_use"], Value::Bool(true));
+        assert_eq!(and2["cell_footprint"], Value::Expression("and".to_string()));
+    }
+
+    #[test]
+    fn test_merge_keep_existing() {
+        let (mut slow, fast) = corners();
+        slow.merge(fast, MergeStrategy::KeepExisting).unwrap();
+        let and2 = &slow["lib"].cells["AND2"].simple_attributes;
+        assert_eq!(and2["area"], Value::Float(1.0));
+        assert_eq!(and2["dont_use"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_merge_repeated_subgroups() {
+        let pin = |contents: &str| {
+            crate::parse_lib(contents).unwrap()["lib"].cells["INV"].pins["o"]
+                .clone()
+                .into_group()
+        };
+        let mut slow = pin(r#"library(lib) { cell(INV) { pin(o) {
+            timing() { related_pin : "a"; }
+            timing() { related_pin : "b"; }
+        } } }"#);
+        let fast = pin(r#"library(lib) { cell(INV) { pin(o) {
+            timing() { timing_sense : positive_unate; }
+            timing() { timing_sense : negative_unate; related_pin : "c"; }
+            timing() { related_pin : "d"; }
+        } } }"#);
+        let err = slow
+            .clone()
+            .merge(fast.clone(), MergeStrategy::Error)
+            .unwrap_err();
+        assert_eq!(err.path, "timing()[1]/related_pin");
+
+        slow.merge(fast, MergeStrategy::KeepExisting).unwrap();
+        let timings: Vec<(&Value, Option<&Value>)> = slow
+            .groups
+            .iter()
+            .map(|g| {
+                (
+                    &g.simple_attributes["related_pin"],
+                    g.simple_attribute("timing_sense"),
+                )
+            })
+            .collect();
+        let text = |s: &str| Value::String(s.to_string());
+        let sense = |s: &str| Value::Expression(s.to_string());
+        assert_eq!(
+            timings,
+            vec![
+                (&text("a"), Some(&sense("positive_unate"))),
+                (&text("b"), Some(&sense("negative_unate"))),
+                (&text("d"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flatten_subgroup() {
+        let mut pin = crate::parse_lib(
+            r#"library(lib) {
+                cell(INV) {
+                    pin(o) {
+                        direction : output;
+                        wrapper(w) {
+                            related_pin : "a";
+                            direction : input;
+                            rise_power(scalar) { values ("1"); }
+                        }
+                        timing() { related_pin : "a"; }
+                    }
+                }
+            }"#,
+        )
+        .unwrap()["lib"]
+            .cells["INV"]
+            .pins["o"]
+            .clone()
+            .into_group();
+        let original = pin.clone();
+        assert_eq!(
+            pin.flatten_subgroup("wrapper", "w", MergeStrategy::Error, false),
+            Err(MergeConflict {
+                path: "direction".to_string()
+            })
+        );
+        assert_eq!(pin, original);
+
+        assert_eq!(
+            pin.flatten_subgroup("wrapper", "w", MergeStrategy::KeepExisting, false),
+            Ok(true)
+        );
+        assert_eq!(
+            pin.simple_attribute("related_pin"),
+            Some(&Value::String("a".to_string()))
+        );
+        assert_eq!(
+            pin.simple_attribute("direction"),
+            Some(&Value::Expression("output".to_string()))
+        );
+        let types: Vec<&str> = pin.groups.iter().map(|g| g.type_.as_str()).collect();
+        assert_eq!(types, vec!["rise_power", "timing"]);
+        assert_eq!(
+            pin.flatten_subgroup("wrapper", "w", MergeStrategy::Overwrite, false),
+            Ok(false)
+        );
+
+        let mut prefixed = original;
+        prefixed
+            .flatten_subgroup("wrapper", "w", MergeStrategy::Error, true)
+            .unwrap();
+        assert!(prefixed.simple_attributes.contains_key("w_direction"));
+        assert!(prefixed.simple_attributes.contains_key("direction"));
+    }
+
+    #[test]
+    fn test_merge_error() {
+        let (mut slow, fast) = corners();
+        let original = slow.clone();
+        let err = slow.merge(fast, MergeStrategy::Error).unwrap_err();
+        assert_eq!(err.path, "cell(AND2)/area");
+        assert_eq!(slow, original);
+
+        let mut cell = Group::new("cell", "AND2");
+        cell.groups.push(Group::new("pin", "a"));
+        let mut other = Group::new("cell", "AND2");
+        other.groups.push(Group::new("pin", "a"));
+        other.groups.push(Group::new("pin", "b"));
+        cell.merge(other, MergeStrategy::Error).unwrap();
+        assert_eq!(cell.groups.len(), 2);
+    }
+}
@@ -0,0 +1,133 @@
+//! Generic tree of maps, lists, and scalars built from [`Group`]s
+//!
+//! [`Node`] has the same shape as JSON, so it can be handed to templating engines and other
+//! tools that take arbitrary data. With the `serde` feature it implements `Serialize`, writing
+//! each variant as the plain JSON value it holds.
+
+use std::collections::BTreeMap;
+
+use crate::ast::Value;
+use crate::liberty::Group;
+
+/// Map, list, or scalar value
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+pub enum Node {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    List(Vec<Node>),
+    /// Map with keys in sorted order
+    Map(BTreeMap<String, Node>),
+}
+
+impl From<&Value> for Node {
+    /// Floats become numbers, strings and expressions become strings, and float groups become
+    /// lists of numbers
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Bool(v) => Node::Bool(*v),
+            Value::Float(v) => Node::Number(*v),
+            Value::String(v) | Value::Expression(v) => Node::String(v.clone()),
+            Value::FloatGroup(v) => Node::List(v.iter().copied().map(Node::Number).collect()),
+        }
+    }
+}
+
+impl Group {
+    /// Convert the group and everything in it into a [`Node`] tree
+    ///
+    /// Each group becomes a map with its `type`, `name`, `attributes`, and `groups`. Simple
+    /// attributes map to their value and complex attributes to a list of their values.
+    /// Sub-groups are a list in their original order.
+    pub fn to_template_context(&self) -> Node {
+        let mut attributes: BTreeMap<String, Node> = self
+            .simple_attributes
+            .iter()
+            .map(|(name, value)| (name.clone(), Node::from(value)))
+            .collect();
+        attributes.extend(self.complex_attributes.iter().map(|(name, values)| {
+            (
+                name.clone(),
+                Node::List(values.iter().map(Node::from).collect()),
+            )
+        }));
+        let mut map = BTreeMap::new();
+        map.insert("type".to_string(), Node::String(self.type_.clone()));
+        map.insert("name".to_string(), Node::String(self.name.clone()));
+        map.insert("attributes".to_string(), Node::Map(attributes));
+        map.insert(
+            "groups".to_string(),
+            Node::List(self.groups.iter().map(Group::to_template_context).collect()),
+        );
+        Node::Map(map)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::liberty::ToGroup;
+
+    fn and2() -> Group {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    pin_opposite ("a", "b");
+                    pin(o) { function : "A & B"; }
+                }
+            }"#,
+        )
+        .unwrap();
+        liberty["foo"].cells["AND2"].clone().into_group()
+    }
+
+    #[test]
+    fn test_to_template_context() {
+        let node = and2().to_template_context();
+        let map = match &node {
+            Node::Map(map) => map,
+            _ => panic!("Expected a map"),
+        };
+        assert_eq!(map["type"], Node::String("cell".to_string()));
+        assert_eq!(
+            map["attributes"],
+            Node::Map(
+                vec![
+                    ("area".to_string(), Node::Number(1.0)),
+                    (
+                        "pin_opposite".to_string(),
+                        Node::List(vec![
+                            Node::String("a".to_string()),
+                            Node::String("b".to_string())
+                        ])
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert!(matches!(&map["groups"], Node::List(groups) if groups.len() == 1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_value(and2().to_template_context()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "cell",
+                "name": "AND2",
+                "attributes": {"area": 1.0, "pin_opposite": ["a", "b"]},
+                "groups": [{
+                    "type": "pin",
+                    "name": "o",
+                    "attributes": {"function": "A & B"},
+                    "groups": [],
+                }],
+            })
+        );
+    }
+}
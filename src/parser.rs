@@ -1,46 +1,248 @@
-use crate::ast::{GroupItem, Value};
+use std::borrow::Cow;
+
+use crate::ast::{GroupItem, GroupItemRef, ValueRef};
+use crate::function::BoolExpr;
+use crate::lint::KNOWN_ATTRIBUTES;
 
 use nom::{
     branch::alt,
-    bytes::complete::{is_a, is_not, tag, take_until, take_while},
-    character::complete::{alpha1, char, line_ending, multispace0, one_of},
-    combinator::{all_consuming, cut, map, map_res, opt, peek, recognize},
-    error::{context, ParseError},
-    multi::{fold_many0, separated_list},
-    number::complete::double,
+    bytes::complete::{is_a, is_not, tag, tag_no_case, take_until, take_while},
+    character::complete::{
+        alpha1, char, digit1, hex_digit1, line_ending, multispace0, multispace1, one_of, space0,
+    },
+    combinator::{all_consuming, cut, map, map_res, opt, peek, recognize, verify},
+    error::{context, ErrorKind, ParseError},
+    multi::{fold_many0, many0, separated_list, separated_nonempty_list},
+    number::complete::{double, recognize_float},
     sequence::{delimited, preceded, terminated, tuple},
-    IResult,
+    Err, IResult,
 };
 
-fn underscore_tag<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
+// Characters that may follow a simple value
+const VALUE_TERMINATORS: &str = ",; \t\r\n)}";
+
+/// Deepest group nesting accepted by default, counting a library as depth 1
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+// Context of the error returned for input nested deeper than `ParseConfig::max_depth`
+pub(crate) const MAX_DEPTH_EXCEEDED: &str = "maximum nesting depth exceeded";
+
+/// Options for [`parse_lib_with_config`](crate::parse_lib_with_config)
+///
+/// The default options accept only standard Liberty syntax. Each option relaxes the syntax to
+/// salvage files written by non-conforming tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseConfig {
+    /// Accept attribute and group names starting with `_`, such as `_internal : 1;`
+    pub leading_underscore: bool,
+    /// Accept `,` in place of the `;` ending a simple attribute, such as `area : 1,`
+    pub comma_terminator: bool,
+    /// Accept empty entries in a group's name list, such as `pin(A,,B)`, dropping them
+    pub empty_group_names: bool,
+    /// Deepest group nesting to accept, counting a library as depth 1
+    ///
+    /// Groups are parsed recursively, so this bounds stack use. Parentheses in expressions and
+    /// functions, and `!` in functions, each count as a further level. Deeper input fails to
+    /// parse with an error whose [`Error::kind`](crate::Error::kind) is
+    /// [`ParseErrorKind::MaxDepthExceeded`](crate::ParseErrorKind::MaxDepthExceeded). Defaults to
+    /// [`DEFAULT_MAX_DEPTH`].
+    pub max_depth: usize,
+    /// Characters that may prefix an identifier in an attribute value, such as the `$` in
+    /// `slew : $SLEW;` used by some vendor extensions. Empty by default
+    pub identifier_sigils: &'static str,
+    /// Collapse each run of whitespace to a single space and trim the ends of the quoted
+    /// `function`, `three_state`, and `x_function` expressions, after joining line
+    /// continuations, so wrapped functions read as one clean line
+    pub normalize_functions: bool,
+    /// Accept floats that overflow to infinity, such as `1e999`, instead of failing to parse
+    pub non_finite_floats: bool,
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        ParseConfig {
+            leading_underscore: false,
+            comma_terminator: false,
+            empty_group_names: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            identifier_sigils: "",
+            normalize_functions: false,
+            non_finite_floats: false,
+        }
+    }
+}
+
+impl ParseConfig {
+    /// Options with every relaxation enabled, accepting `@` and `$` as identifier sigils
+    pub fn lenient() -> Self {
+        ParseConfig {
+            leading_underscore: true,
+            comma_terminator: true,
+            empty_group_names: true,
+            identifier_sigils: "@$",
+            non_finite_floats: true,
+            ..ParseConfig::default()
+        }
+    }
+}
+
+// Options and nesting depth of the running parse, passed by value to every parser that needs
+// either
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ParseState {
+    config: ParseConfig,
+    // Number of groups and parenthesized sub-expressions enclosing the current input
+    depth: usize,
+}
+
+impl ParseState {
+    pub(crate) fn new(config: &ParseConfig) -> Self {
+        ParseState {
+            config: *config,
+            depth: 0,
+        }
+    }
+
+    // State for parsing one level further in, failing if that's deeper than
+    // `ParseConfig::max_depth`
+    fn enter<'a, E: ParseError<&'a str>>(self, input: &'a str) -> Result<Self, Err<E>> {
+        if self.depth >= self.config.max_depth {
+            return Err(Err::Failure(E::add_context(
+                input,
+                MAX_DEPTH_EXCEEDED,
+                E::from_error_kind(input, ErrorKind::TooLarge),
+            )));
+        }
+        Ok(ParseState {
+            depth: self.depth + 1,
+            ..self
+        })
+    }
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn underscore_tag<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let first = |input| {
+        if state.config.leading_underscore {
+            alt((alpha1, tag("_")))(input)
+        } else {
+            alpha1(input)
+        }
+    };
     context(
         "underscore_tag",
-        recognize(preceded(
-            alpha1,
-            take_while(|c: char| c.is_alphanumeric() || c.eq_ignore_ascii_case(&'_')),
-        )),
+        recognize(preceded(first, take_while(is_tag_char))),
     )(input)
 }
 
-fn quoted_floats<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, Vec<f64>, E> {
+// Attribute or group whose name starts with something other than a letter, such as `1area : 1;`.
+// Fails without backtracking so the error says what's wrong.
+fn misnamed_statement<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, GroupItemRef<'a>, E> {
+    let (rest, _) = preceded(one_of("0123456789_"), take_while(is_tag_char))(input)?;
+    let (rest, _) = multispace0(rest)?;
+    if rest.starts_with(':') || rest.starts_with('(') {
+        Err(Err::Failure(E::add_context(
+            input,
+            "attribute/group names must start with a letter",
+            E::from_error_kind(input, ErrorKind::Alpha),
+        )))
+    } else {
+        Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify)))
+    }
+}
+
+// Decimal float, failing without backtracking if it overflows to infinity like `1e999` unless
+// `ParseConfig::non_finite_floats` is set. Words like `nan` and `inf` aren't floats at all.
+fn finite_float<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, f64, E> {
+    let (rest, digits) = recognize_float(input)?;
+    let value = digits
+        .parse::<f64>()
+        .map_err(|_| Err::Error(E::from_error_kind(input, ErrorKind::Float)))?;
+    if value.is_finite() || state.config.non_finite_floats {
+        Ok((rest, value))
+    } else {
+        Err(Err::Failure(E::add_context(
+            input,
+            "finite float",
+            E::from_error_kind(input, ErrorKind::Float),
+        )))
+    }
+}
+
+// Floats are separated by commas, or by whitespace alone if there are no commas
+fn quoted_floats<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, Vec<f64>, E> {
+    let closing_quote = || preceded(multispace0, char('\"'));
+    let float = |input| preceded(multispace0, move |input| finite_float(state, input))(input);
     context(
         "quoted floats",
         preceded(
             char('\"'),
-            terminated(
-                separated_list(
-                    preceded(multispace0, char(',')),
-                    preceded(multispace0, double),
+            alt((
+                terminated(
+                    separated_list(preceded(multispace0, char(',')), float),
+                    closing_quote(),
                 ),
-                char('\"'),
-            ),
+                terminated(separated_list(multispace1, float), closing_quote()),
+            )),
+        ),
+    )(input)
+}
+
+fn radix_integer<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, f64, E> {
+    context(
+        "radix integer",
+        map(
+            alt((
+                map_res(preceded(tag_no_case("0x"), hex_digit1), |s| {
+                    u64::from_str_radix(s, 16)
+                }),
+                map_res(preceded(tag_no_case("0b"), is_a("01")), |s| {
+                    u64::from_str_radix(s, 2)
+                }),
+            )),
+            |v| v as f64,
         ),
     )(input)
 }
 
-fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
+// Identifier in an expression, optionally prefixed by one of `ParseConfig::identifier_sigils`
+fn identifier<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let sigils = state.config.identifier_sigils;
+    let tag = move |input| underscore_tag(state, input);
+    if sigils.is_empty() {
+        tag(input)
+    } else {
+        recognize(preceded(opt(one_of(sigils)), tag))(input)
+    }
+}
+
+fn expression<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let sub_expression = |input| {
+        let state = state.enter(input)?;
+        terminated(move |input| expression(state, input), char(')'))(input)
+    };
     context("expression", move |input| {
-        recognize(separated_list(
+        recognize(separated_nonempty_list(
             // operator
             preceded(multispace0, is_a("+-*/")),
             // operand
@@ -50,9 +252,9 @@ fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str,
                     opt(is_a("-")),
                     alt((
                         // sub expression
-                        preceded(char('('), cut(terminated(expression, char(')')))),
+                        preceded(char('('), cut(sub_expression)),
                         // identifier
-                        underscore_tag,
+                        move |input| identifier(state, input),
                         // constant
                         recognize(double),
                     )),
@@ -62,93 +264,271 @@ fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str,
     })(input)
 }
 
-fn quoted_string<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
+fn quoted_string<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
     context(
         "quoted string",
         preceded(char('\"'), cut(terminated(is_not("\""), char('\"')))),
     )(input)
 }
 
-fn boolean<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, bool, E> {
+// Join lines continued with a trailing `\` inside a quoted string, dropping the `\`, the line
+// ending, and the next line's indentation
+fn join_continuations(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+    let mut joined = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(idx) = rest.find('\\') {
+        let (head, tail) = rest.split_at(idx);
+        let after = tail[1..].trim_start_matches([' ', '\t']);
+        let after = after.strip_prefix('\r').unwrap_or(after);
+        joined.push_str(head);
+        match after.strip_prefix('\n') {
+            Some(next) => rest = next.trim_start_matches([' ', '\t']),
+            None => {
+                joined.push('\\');
+                rest = &tail[1..];
+            }
+        }
+    }
+    joined.push_str(rest);
+    Cow::Owned(joined)
+}
+
+fn boolean<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, bool, E> {
     map_res(alpha1, |s: &str| s.parse::<bool>())(input)
 }
 
-fn simple_attr_value<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, Value, E> {
+fn simple_attr_value<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, ValueRef<'a>, E> {
     context(
         "simple attr value",
         preceded(
             multispace0,
             alt((
-                map(quoted_floats, Value::FloatGroup),
-                map(quoted_string, |s| Value::String(s.to_string())),
-                map(terminated(double, peek(one_of(",; \t)"))), Value::Float),
-                map(boolean, Value::Bool),
-                map(map(expression, String::from), Value::Expression),
+                map(
+                    move |input| quoted_floats(state, input),
+                    ValueRef::FloatGroup,
+                ),
+                map(quoted_string, |s| ValueRef::String(join_continuations(s))),
+                map(
+                    terminated(radix_integer, peek(one_of(VALUE_TERMINATORS))),
+                    ValueRef::Float,
+                ),
+                map(
+                    terminated(
+                        move |input| finite_float(state, input),
+                        peek(one_of(VALUE_TERMINATORS)),
+                    ),
+                    ValueRef::Float,
+                ),
+                map(
+                    terminated(boolean, peek(one_of(VALUE_TERMINATORS))),
+                    ValueRef::Bool,
+                ),
+                map(move |input| expression(state, input), ValueRef::Expression),
             )),
         ),
     )(input)
 }
 
-fn simple_attribute<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, GroupItem, E> {
+// Semicolon ending a simple attribute, which may be left out before the group's closing brace
+fn attribute_end<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, (), E> {
+    let terminator = |input| {
+        if state.config.comma_terminator {
+            alt((char(';'), char(',')))(input)
+        } else {
+            char(';')(input)
+        }
+    };
+    preceded(
+        multispace0,
+        alt((map(terminator, |_| ()), map(peek(char('}')), |_| ()))),
+    )(input)
+}
+
+// Attributes holding boolean expressions, normalized with `ParseConfig::normalize_functions`
+const FUNCTION_ATTRIBUTES: [&str; 3] = ["function", "three_state", "x_function"];
+
+fn simple_attribute<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, GroupItemRef<'a>, E> {
     context(
         "simple attr",
         map(
             tuple((
-                preceded(multispace0, underscore_tag),
+                preceded(multispace0, move |input| underscore_tag(state, input)),
                 preceded(multispace0, char(':')),
-                cut(preceded(multispace0, simple_attr_value)),
-                preceded(multispace0, char(';')),
+                cut(preceded(multispace0, move |input| {
+                    simple_attr_value(state, input)
+                })),
+                move |input| attribute_end(state, input),
             )),
-            |(name, _, value, _)| GroupItem::SimpleAttr(name.to_string(), value),
+            |(name, _, value, _)| match value {
+                ValueRef::String(s)
+                    if state.config.normalize_functions && FUNCTION_ATTRIBUTES.contains(&name) =>
+                {
+                    let normalized = s.split_whitespace().collect::<Vec<_>>().join(" ");
+                    GroupItemRef::SimpleAttr(name, ValueRef::String(Cow::Owned(normalized)))
+                }
+                value => GroupItemRef::SimpleAttr(name, value),
+            },
         ),
     )(input)
 }
 
+// Whitespace and block comments between the values of a complex attribute, which some tools
+// use to label the rows of a table
+fn value_gap<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(many0(alt((multispace1, comment))))(input)
+}
+
+// `\` ending a line that continues on the next, allowing blanks between the two
+fn line_continuation<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((tag("\\"), space0, line_ending)))(input)
+}
+
+// Values separated by `separator`, with optional line continuations before the first and after
+// the last value
+fn complex_value_list<'a, E, F, O>(
+    state: ParseState,
+    separator: F,
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<ValueRef<'a>>, E>
+where
+    E: ParseError<&'a str>,
+    F: Fn(&'a str) -> IResult<&'a str, O, E>,
+{
+    delimited(
+        opt(tuple((value_gap, line_continuation))),
+        separated_list(
+            separator,
+            preceded(value_gap, move |input| simple_attr_value(state, input)),
+        ),
+        opt(tuple((value_gap, line_continuation))),
+    )
+}
+
+// Comma after the last item of a list, optionally followed by a line continuation
+fn trailing_comma<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        value_gap,
+        tag(","),
+        opt(tuple((value_gap, line_continuation))),
+    )))(input)
+}
+
+// Values are separated by commas, or by whitespace alone if there are no commas. A trailing
+// comma after the last value is dropped.
 fn complex_attribute_values<'a, E: ParseError<&'a str>>(
+    state: ParseState,
     input: &'a str,
-) -> IResult<&str, Vec<Value>, E> {
+) -> IResult<&'a str, Vec<ValueRef<'a>>, E> {
+    let closing_paren = || preceded(value_gap, tag(")"));
     context(
         "complex values",
-        delimited(
-            preceded(multispace0, tag("(")),
-            delimited(
-                opt(tuple((multispace0, tag("\\"), line_ending))),
-                separated_list(
-                    alt((
-                        map(
-                            tuple((multispace0, tag(","), multispace0, tag("\\"), line_ending)),
-                            |_| Some(1),
-                        ),
-                        map(tuple((multispace0, tag(","))), |_| Some(1)),
-                        map(
-                            tuple((multispace0, tag("\\"), line_ending, multispace0, tag(","))),
-                            |_| Some(1),
+        preceded(
+            preceded(value_gap, tag("(")),
+            alt((
+                terminated(
+                    map(
+                        verify(
+                            tuple((
+                                complex_value_list(
+                                    state,
+                                    alt((
+                                        map(
+                                            tuple((
+                                                value_gap,
+                                                tag(","),
+                                                value_gap,
+                                                line_continuation,
+                                            )),
+                                            |_| Some(1),
+                                        ),
+                                        map(tuple((value_gap, tag(","))), |_| Some(1)),
+                                        map(
+                                            tuple((
+                                                value_gap,
+                                                line_continuation,
+                                                value_gap,
+                                                tag(","),
+                                            )),
+                                            |_| Some(1),
+                                        ),
+                                    )),
+                                ),
+                                opt(trailing_comma),
+                            )),
+                            |(values, comma): &(Vec<ValueRef>, Option<&str>)| {
+                                comma.is_none() || !values.is_empty()
+                            },
                         ),
-                    )),
-                    preceded(multispace0, simple_attr_value),
+                        |(values, _)| values,
+                    ),
+                    closing_paren(),
                 ),
-                opt(tuple((multispace0, tag("\\"), line_ending))),
-            ),
-            preceded(multispace0, tag(")")),
+                terminated(
+                    complex_value_list(
+                        state,
+                        alt((
+                            recognize(tuple((value_gap, line_continuation, value_gap))),
+                            verify(value_gap, |gap: &str| !gap.is_empty()),
+                        )),
+                    ),
+                    closing_paren(),
+                ),
+            )),
         ),
     )(input)
 }
 
-fn complex_attribute<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, GroupItem, E> {
+fn complex_attribute<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, GroupItemRef<'a>, E> {
     context(
         "complex attr",
         map(
             tuple((
-                preceded(multispace0, underscore_tag),
-                preceded(multispace0, complex_attribute_values),
+                preceded(multispace0, move |input| underscore_tag(state, input)),
+                preceded(multispace0, move |input| {
+                    complex_attribute_values(state, input)
+                }),
                 preceded(multispace0, char(';')),
             )),
-            |(name, value, _)| GroupItem::ComplexAttr(name.to_string(), value),
+            |(name, value, _)| GroupItemRef::ComplexAttr(name, value),
         ),
     )(input)
 }
 
-fn comment<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E> {
+// Statement that isn't an attribute or group, such as `foo_bar_statement xyz;`, kept as raw
+// text up to and including its `;` or the `}` closing its block. A well-known attribute name
+// is never taken as one, so a typo such as `area 1;` still fails to parse
+fn unknown_statement<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let (rest, name) = underscore_tag(state, input)?;
+    let (rest, _) = multispace0(rest)?;
+    let known = KNOWN_ATTRIBUTES.iter().any(|(known, _)| *known == name);
+    if !known && !rest.starts_with(':') && !rest.starts_with('(') {
+        let remaining = skip_item(input);
+        let raw = &input[..input.len() - remaining.len()];
+        if raw.ends_with(';') || raw.ends_with('}') {
+            return Ok((remaining, raw));
+        }
+    }
+    Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify)))
+}
+
+fn comment<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
     context(
         "comment",
         recognize(delimited(tag("/*"), take_until("*/"), tag("*/"))),
@@ -156,65 +536,170 @@ fn comment<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, &str, E>
 }
 
 fn parse_group_body<'a, E: ParseError<&'a str>>(
+    state: ParseState,
     input: &'a str,
-) -> IResult<&str, Vec<GroupItem>, E> {
+) -> IResult<&'a str, Vec<GroupItemRef<'a>>, E> {
     context(
         "group body",
         fold_many0(
             context(
                 "folding items",
                 alt((
+                    map(preceded(space0, comment), GroupItemRef::TrailingComment),
+                    map(preceded(multispace0, comment), GroupItemRef::Comment),
+                    preceded(multispace0, move |input| parse_group(state, input)),
+                    preceded(multispace0, move |input| simple_attribute(state, input)),
+                    preceded(multispace0, move |input| complex_attribute(state, input)),
                     map(
-                        map(preceded(multispace0, comment), String::from),
-                        GroupItem::Comment,
+                        preceded(
+                            multispace0,
+                            context("unknown statement", move |input| {
+                                unknown_statement(state, input)
+                            }),
+                        ),
+                        GroupItemRef::Unknown,
                     ),
-                    preceded(multispace0, parse_group),
-                    preceded(multispace0, simple_attribute),
-                    preceded(multispace0, complex_attribute),
+                    preceded(multispace0, misnamed_statement),
                 )),
             ),
             Vec::new(),
             |mut acc: Vec<_>, item| {
-                acc.push(item);
+                push_item(&mut acc, item);
                 acc
             },
         ),
     )(input)
 }
-fn parse_group<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, GroupItem, E> {
+
+// Add an item to a group body, keeping a same-line comment as trailing only after an attribute
+fn push_item<'a>(items: &mut Vec<GroupItemRef<'a>>, item: GroupItemRef<'a>) {
+    let item = match item {
+        GroupItemRef::TrailingComment(c) => match items.last() {
+            Some(GroupItemRef::SimpleAttr(_, _)) | Some(GroupItemRef::ComplexAttr(_, _)) => {
+                GroupItemRef::TrailingComment(c)
+            }
+            _ => GroupItemRef::Comment(c),
+        },
+        item => item,
+    };
+    items.push(item);
+}
+// Group argument, such as a pin name, including a bus index or range like `A[3]` or `A[7:0]`
+fn group_name<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        move |input| underscore_tag(state, input),
+        opt(delimited(
+            char('['),
+            tuple((digit1, opt(preceded(char(':'), digit1)))),
+            char(']'),
+        )),
+    )))(input)
+}
+
+// Comma-separated names in a group header, allowing a trailing comma such as `pin(A,)`
+fn group_names<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, Vec<&'a str>, E> {
+    let entry = opt(preceded(
+        multispace0,
+        alt((quoted_string, move |input| group_name(state, input))),
+    ));
+    let (mut rest, first) = entry(input)?;
+    let mut entries = vec![first];
+    while let Ok((after_comma, _)) = preceded(multispace0, char::<_, E>(','))(rest) {
+        let (after_entry, name) = entry(after_comma)?;
+        entries.push(name);
+        rest = after_entry;
+    }
+    let len = entries.len();
+    if len > 1 && entries.last() == Some(&None) {
+        entries.pop();
+    }
+    if len > 1 && entries.contains(&None) && !state.config.empty_group_names {
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify)));
+    }
+    Ok((rest, entries.into_iter().flatten().collect()))
+}
+
+// Group type and name, up to and including the opening brace
+fn group_header<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, (&'a str, Cow<'a, str>), E> {
+    terminated(
+        tuple((
+            preceded(multispace0, move |input| underscore_tag(state, input)),
+            preceded(
+                preceded(multispace0, char('(')),
+                terminated(
+                    map(
+                        move |input| group_names(state, input),
+                        |vals: Vec<&str>| match vals.as_slice() {
+                            [] => Cow::Borrowed(""),
+                            [name] => Cow::Borrowed(*name),
+                            _ => Cow::Owned(vals.join(",")),
+                        },
+                    ),
+                    preceded(multispace0, char(')')),
+                ),
+            ),
+        )),
+        preceded(multispace0, char('{')),
+    )(input)
+}
+
+fn parse_group<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, GroupItemRef<'a>, E> {
     context(
         "parsing group",
         map(
             tuple((
-                preceded(multispace0, underscore_tag),
-                preceded(
-                    preceded(multispace0, char('(')),
+                move |input| group_header(state, input),
+                cut(|input| {
+                    let state = state.enter(input)?;
                     terminated(
-                        map(
-                            separated_list(
-                                preceded(multispace0, char(',')),
-                                preceded(multispace0, alt((quoted_string, underscore_tag))),
-                            ),
-                            |vals: Vec<&str>| vals.join(","),
-                        ),
-                        preceded(multispace0, char(')')),
-                    ),
-                ),
-                preceded(
-                    preceded(multispace0, char('{')),
-                    cut(terminated(
-                        parse_group_body,
+                        move |input| parse_group_body(state, input),
                         preceded(multispace0, char('}')),
-                    )),
-                ),
+                    )(input)
+                }),
             )),
-            |(gtype, name, body)| GroupItem::Group(gtype.to_string(), name.to_string(), body),
+            |((gtype, name), body)| GroupItemRef::Group(gtype, name, body),
         ),
     )(input)
 }
 
-pub fn parse_libs<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, Vec<GroupItem>, E> {
-    context(
+pub fn parse_libs<'a, E: ParseError<&'a str>>(
+    config: &ParseConfig,
+    input: &'a str,
+) -> IResult<&'a str, Vec<GroupItem>, E> {
+    map(
+        |input| parse_libs_ref(config, input),
+        |libs| libs.into_iter().map(GroupItemRef::into_owned).collect(),
+    )(input)
+}
+
+// Byte order mark written at the start of files by some Windows tools
+const BOM: char = '\u{feff}';
+
+// Whitespace at the end of a file, including form feeds and NULs left by some tools
+fn end_padding(input: &str) -> &str {
+    input.trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+}
+
+pub fn parse_libs_ref<'a, E: ParseError<&'a str>>(
+    config: &ParseConfig,
+    input: &'a str,
+) -> IResult<&'a str, Vec<GroupItemRef<'a>>, E> {
+    let state = ParseState::new(config);
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+    let (input, padding) = input.split_at(end_padding(input).len());
+    let (_, libs) = context(
         "parse_libs",
         all_consuming(terminated(
             fold_many0(
@@ -222,35 +707,457 @@ pub fn parse_libs<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&str, V
                     context(
                         "outer comment",
                         map(
-                            map(delimited(multispace0, comment, multispace0), String::from),
-                            GroupItem::Comment,
+                            delimited(multispace0, comment, multispace0),
+                            GroupItemRef::Comment,
                         ),
                     ),
-                    preceded(multispace0, context("parse_lib", parse_group)),
+                    preceded(
+                        multispace0,
+                        context("parse_lib", move |input| parse_group(state, input)),
+                    ),
                 )),
                 Vec::new(),
                 |mut acc: Vec<_>, item| {
                     match &item {
-                        GroupItem::Group(_, _, _) => acc.push(item),
-                        GroupItem::Comment(_) => {}
-                        GroupItem::SimpleAttr(_, _) => {}
-                        GroupItem::ComplexAttr(_, _) => {}
+                        GroupItemRef::Group(_, _, _) => acc.push(item),
+                        GroupItemRef::Comment(_) | GroupItemRef::TrailingComment(_) => {}
+                        GroupItemRef::SimpleAttr(_, _) => {}
+                        GroupItemRef::ComplexAttr(_, _) => {}
+                        GroupItemRef::Unknown(_) => {}
                     }
                     acc
                 },
             ),
             multispace0,
         )),
+    )(input)?;
+    Ok((&padding[padding.len()..], libs))
+}
+
+fn function_operand<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, BoolExpr, E> {
+    let negated = |input| {
+        let state = state.enter(input)?;
+        function_operand(state, input)
+    };
+    let sub_expression = |input| {
+        let state = state.enter(input)?;
+        terminated(
+            move |input| function_or(state, input),
+            preceded(multispace0, char(')')),
+        )(input)
+    };
+    context(
+        "function operand",
+        preceded(
+            multispace0,
+            alt((
+                map(preceded(char('!'), cut(negated)), |e| {
+                    BoolExpr::Not(Box::new(e))
+                }),
+                map(
+                    tuple((
+                        alt((
+                            preceded(char('('), cut(sub_expression)),
+                            map(char('0'), |_| BoolExpr::Const(false)),
+                            map(char('1'), |_| BoolExpr::Const(true)),
+                            map(
+                                move |input| underscore_tag(state, input),
+                                |s| BoolExpr::Var(s.to_string()),
+                            ),
+                        )),
+                        many0(preceded(multispace0, char('\''))),
+                    )),
+                    |(expr, primes)| {
+                        primes
+                            .iter()
+                            .fold(expr, |acc, _| BoolExpr::Not(Box::new(acc)))
+                    },
+                ),
+            )),
+        ),
+    )(input)
+}
+
+fn fold_binary(
+    operands: Vec<BoolExpr>,
+    op: fn(Box<BoolExpr>, Box<BoolExpr>) -> BoolExpr,
+) -> BoolExpr {
+    let mut iter = operands.into_iter();
+    let first = iter
+        .next()
+        .expect("separated_nonempty_list yields an operand");
+    iter.fold(first, |acc, e| op(Box::new(acc), Box::new(e)))
+}
+
+fn function_xor<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, BoolExpr, E> {
+    map(
+        separated_nonempty_list(preceded(multispace0, char('^')), move |input| {
+            function_operand(state, input)
+        }),
+        |operands| fold_binary(operands, BoolExpr::Xor),
+    )(input)
+}
+
+fn function_and<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, BoolExpr, E> {
+    map(
+        separated_nonempty_list(
+            alt((
+                map(preceded(multispace0, one_of("&*")), |_| ()),
+                // juxtaposition, e.g. `A B`, is also an AND
+                map(
+                    terminated(multispace1, peek(alt((alpha1, tag("("), tag("!"))))),
+                    |_| (),
+                ),
+            )),
+            move |input| function_xor(state, input),
+        ),
+        |operands| fold_binary(operands, BoolExpr::And),
     )(input)
 }
 
+fn function_or<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+) -> IResult<&'a str, BoolExpr, E> {
+    context(
+        "function",
+        map(
+            separated_nonempty_list(preceded(multispace0, one_of("|+")), move |input| {
+                function_and(state, input)
+            }),
+            |operands| fold_binary(operands, BoolExpr::Or),
+        ),
+    )(input)
+}
+
+pub fn parse_function<'a, E: ParseError<&'a str>>(
+    config: &ParseConfig,
+    input: &'a str,
+) -> IResult<&'a str, BoolExpr, E> {
+    let state = ParseState::new(config);
+    all_consuming(terminated(
+        move |input| function_or(state, input),
+        multispace0,
+    ))(input)
+}
+
+// Skip past a malformed item: through the next `;`, over a braced block, or up to the `}`
+// closing the enclosing group
+fn skip_item(input: &str) -> &str {
+    let mut depth = 0;
+    let mut in_quotes = false;
+    for (idx, c) in input.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            ';' if depth == 0 => return &input[idx + 1..],
+            '{' => depth += 1,
+            '}' if depth == 0 => return &input[idx..],
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &input[idx + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+/// Split the input into chunks that each end with a top-level group
+///
+/// Braces inside quoted strings and comments are ignored. Any trailing text after the last
+/// group, such as a comment, is returned as its own chunk.
+#[cfg(feature = "rayon")]
+pub fn split_libraries(input: &str) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut in_comment = false;
+    let mut chars = input.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '*' if in_comment && chars.peek().map(|(_, c)| *c) == Some('/') => {
+                chars.next();
+                in_comment = false;
+            }
+            _ if in_comment => {}
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            '/' if chars.peek().map(|(_, c)| *c) == Some('*') => {
+                chars.next();
+                in_comment = true;
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    chunks.push(&input[start..=idx]);
+                    start = idx + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !input[start..].trim().is_empty() {
+        chunks.push(&input[start..]);
+    }
+    chunks
+}
+
+// Parse a group, recording errors in its body instead of failing
+fn parse_group_lenient<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    input: &'a str,
+    errors: &mut Vec<Err<E>>,
+) -> IResult<&'a str, GroupItemRef<'a>, E> {
+    let (input, (gtype, name)) = group_header(state, input)?;
+    let state = state.enter(input)?;
+    let (input, body) = parse_group_body_lenient(state, input, errors);
+    let input = match preceded(multispace0, char::<&str, E>('}'))(input) {
+        Ok((input, _)) => input,
+        Err(e) => {
+            errors.push(e);
+            input
+        }
+    };
+    Ok((input, GroupItemRef::Group(gtype, name, body)))
+}
+
+// Parse group items until the closing brace, skipping and recording malformed items
+fn parse_group_body_lenient<'a, E: ParseError<&'a str>>(
+    state: ParseState,
+    mut input: &'a str,
+    errors: &mut Vec<Err<E>>,
+) -> (&'a str, Vec<GroupItemRef<'a>>) {
+    let mut items = vec![];
+    loop {
+        if let Ok((rest, c)) = preceded(space0::<&str, E>, comment)(input) {
+            push_item(&mut items, GroupItemRef::TrailingComment(c));
+            input = rest;
+            continue;
+        }
+        let (rest, _) = multispace0::<&str, E>(input).unwrap_or((input, ""));
+        if rest.is_empty() || rest.starts_with('}') {
+            return (input, items);
+        }
+        if let Ok((rest, c)) = comment::<E>(rest) {
+            items.push(GroupItemRef::Comment(c));
+            input = rest;
+            continue;
+        }
+        match parse_group_lenient(state, input, errors) {
+            Ok((rest, group)) => {
+                items.push(group);
+                input = rest;
+                continue;
+            }
+            // Nested too deep, so skip the whole group
+            Err(failure @ Err::Failure(_)) => {
+                errors.push(failure);
+                input = skip_item(rest);
+                continue;
+            }
+            Err(_) => {}
+        }
+        match alt((
+            move |input| simple_attribute(state, input),
+            move |input| complex_attribute(state, input),
+        ))(input)
+        {
+            Ok((rest, item)) => {
+                items.push(item);
+                input = rest;
+            }
+            Err(e) => match unknown_statement::<E>(state, rest) {
+                Ok((rest, raw)) => {
+                    items.push(GroupItemRef::Unknown(raw));
+                    input = rest;
+                }
+                Err(_) => {
+                    errors.push(match misnamed_statement::<E>(rest) {
+                        Err(failure @ Err::Failure(_)) => failure,
+                        _ => e,
+                    });
+                    input = skip_item(rest);
+                }
+            },
+        }
+    }
+}
+
+/// Parse every library that can be parsed, skipping malformed items
+///
+/// Returns the parsed libraries along with an error for each skipped item.
+pub fn parse_libs_lenient<'a, E: ParseError<&'a str>>(
+    config: &ParseConfig,
+    input: &'a str,
+) -> (Vec<GroupItem>, Vec<Err<E>>) {
+    let state = ParseState::new(config);
+    let mut input = input.strip_prefix(BOM).unwrap_or(input);
+    let mut libs = vec![];
+    let mut errors = vec![];
+    loop {
+        let (rest, _) = multispace0::<&str, E>(input).unwrap_or((input, ""));
+        if end_padding(rest).is_empty() {
+            return (libs, errors);
+        }
+        if let Ok((rest, _)) = comment::<E>(rest) {
+            input = rest;
+            continue;
+        }
+        match parse_group_lenient(state, input, &mut errors) {
+            Ok((rest, lib)) => {
+                libs.push(lib.into_owned());
+                input = rest;
+            }
+            Err(e) => {
+                errors.push(e);
+                // skip_item stops at a stray closing brace, so step past it
+                input = skip_item(rest).trim_start_matches('}');
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nom::{
-        error::{convert_error, ErrorKind, VerboseError},
-        Err,
-    };
+    use crate::ast::Value;
+    use nom::error::{convert_error, ErrorKind, VerboseError};
+
+    // Owned versions of the parsers, to compare against `GroupItem` and `Value`, run with the
+    // default options unless a `_with` variant is given others
+
+    fn state() -> ParseState {
+        ParseState::new(&ParseConfig::default())
+    }
+
+    fn simple_attr_value<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Value, E> {
+        map(
+            move |input| super::simple_attr_value(state(), input),
+            ValueRef::into_owned,
+        )(input)
+    }
+
+    fn simple_attribute<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> IResult<&'a str, GroupItem, E> {
+        simple_attribute_with(ParseConfig::default(), input)
+    }
+
+    fn simple_attribute_with<'a, E: ParseError<&'a str>>(
+        config: ParseConfig,
+        input: &'a str,
+    ) -> IResult<&'a str, GroupItem, E> {
+        let state = ParseState::new(&config);
+        map(
+            move |input| super::simple_attribute(state, input),
+            GroupItemRef::into_owned,
+        )(input)
+    }
+
+    fn complex_attribute_values<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> IResult<&'a str, Vec<Value>, E> {
+        map(
+            move |input| super::complex_attribute_values(state(), input),
+            |values| values.into_iter().map(ValueRef::into_owned).collect(),
+        )(input)
+    }
+
+    fn complex_attribute<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> IResult<&'a str, GroupItem, E> {
+        map(
+            move |input| super::complex_attribute(state(), input),
+            GroupItemRef::into_owned,
+        )(input)
+    }
+
+    fn parse_group<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, GroupItem, E> {
+        parse_group_with(ParseConfig::default(), input)
+    }
+
+    fn parse_group_with<'a, E: ParseError<&'a str>>(
+        config: ParseConfig,
+        input: &'a str,
+    ) -> IResult<&'a str, GroupItem, E> {
+        let state = ParseState::new(&config);
+        map(
+            move |input| super::parse_group(state, input),
+            GroupItemRef::into_owned,
+        )(input)
+    }
+
+    fn quoted_floats<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Vec<f64>, E> {
+        super::quoted_floats(state(), input)
+    }
+
+    fn underscore_tag<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+        super::underscore_tag(state(), input)
+    }
+
+    fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+        super::expression(state(), input)
+    }
+
+    fn parse_libs<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> IResult<&'a str, Vec<GroupItem>, E> {
+        super::parse_libs(&ParseConfig::default(), input)
+    }
+
+    fn parse_libs_lenient<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> (Vec<GroupItem>, Vec<Err<E>>) {
+        super::parse_libs_lenient(&ParseConfig::default(), input)
+    }
+
+    fn group_header<'a, E: ParseError<&'a str>>(
+        input: &'a str,
+    ) -> IResult<&'a str, (&'a str, Cow<'a, str>), E> {
+        super::group_header(state(), input)
+    }
+
+    #[test]
+    fn test_quoted_floats_exponents() {
+        assert_eq!(
+            quoted_floats::<(&str, ErrorKind)>(r#"".5, 1.5e-3, -2e2""#),
+            Ok(("", vec![0.5, 1.5e-3, -200.0]))
+        );
+        assert_eq!(
+            quoted_floats::<(&str, ErrorKind)>(r#""1.0e-3, 2.0E+3, -.25""#),
+            Ok(("", vec![1.0e-3, 2.0e3, -0.25]))
+        );
+        assert_eq!(
+            complex_attribute_values::<(&str, ErrorKind)>(
+                r#"( "1e-3, 2e-3", ".5, 1.5e-3, -2e2" )"#
+            ),
+            Ok((
+                "",
+                vec![
+                    Value::FloatGroup(vec![1e-3, 2e-3]),
+                    Value::FloatGroup(vec![0.5, 1.5e-3, -200.0]),
+                ]
+            ))
+        );
+        assert_eq!(
+            simple_attr_value::<(&str, ErrorKind)>(" .5e-3;"),
+            Ok((";", Value::Float(0.5e-3)))
+        );
+    }
 
     #[test]
     fn test_complex_attr_values() {
@@ -281,6 +1188,32 @@ mod tests {
             complex_attribute_values::<VerboseError<&str>>("(123,-456)"),
             Ok(("", vec![Value::Float(123.0), Value::Float(-456.0),]))
         );
+        assert_eq!(
+            complex_attribute_values::<VerboseError<&str>>("()"),
+            Ok(("", vec![]))
+        );
+    }
+
+    #[test]
+    fn test_negative_values() {
+        let floats = |vs: &[f64]| vs.iter().copied().map(Value::Float).collect::<Vec<_>>();
+        for (input, expected) in &[
+            ("(-1, -2, -3)", floats(&[-1.0, -2.0, -3.0])),
+            ("(1, -2, 3, -4)", floats(&[1.0, -2.0, 3.0, -4.0])),
+            ("(-0.5, .5)", floats(&[-0.5, 0.5])),
+            ("(-0.5,-.5)", floats(&[-0.5, -0.5])),
+            ("( -1 , -2 )", floats(&[-1.0, -2.0])),
+            ("(-1 -2 3)", floats(&[-1.0, -2.0, 3.0])),
+            ("(-1e-3, \\\n -2E2)", floats(&[-1e-3, -200.0])),
+            ("(-1, -2,)", floats(&[-1.0, -2.0])),
+        ] {
+            assert_eq!(
+                complex_attribute_values::<VerboseError<&str>>(input),
+                Ok(("", expected.clone())),
+                "{}",
+                input
+            );
+        }
     }
 
     #[test]
@@ -297,6 +1230,308 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bom_and_end_padding() {
+        let bytes = b"\xef\xbb\xbflibrary(foo) { area : 1; }\n\x0c\0";
+        let input = std::str::from_utf8(bytes).unwrap();
+        let (rest, libs) = parse_libs::<VerboseError<&str>>(input).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(
+            libs,
+            vec![GroupItem::Group(
+                "library".to_string(),
+                "foo".to_string(),
+                vec![GroupItem::SimpleAttr("area".to_string(), Value::Float(1.0))],
+            )]
+        );
+        let (libs, errors) = parse_libs_lenient::<VerboseError<&str>>(input);
+        assert_eq!((libs.len(), errors.len()), (1, 0));
+
+        let err = crate::parse_lib("\u{feff}library(foo) {\n  area : ;\n}").unwrap_err();
+        assert_eq!(err.location().map(|(line, _)| line), Some(2));
+        assert!(parse_libs::<VerboseError<&str>>("library(foo) {}\0 junk").is_err());
+    }
+
+    #[test]
+    fn test_misnamed_statement() {
+        for input in &[
+            "library(foo) { 1area : 1; }",
+            "library(foo) { _internal(x) {} }",
+        ] {
+            match parse_group::<VerboseError<&str>>(input) {
+                Err(Err::Failure(e)) => assert!(convert_error(input, e)
+                    .contains("attribute/group names must start with a letter")),
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+        assert_eq!(
+            simple_attribute_with::<VerboseError<&str>>(ParseConfig::lenient(), "_internal : 1;"),
+            Ok((
+                "",
+                GroupItem::SimpleAttr("_internal".to_string(), Value::Float(1.0))
+            ))
+        );
+        assert!(simple_attribute::<VerboseError<&str>>("_internal : 1;").is_err());
+    }
+
+    #[test]
+    fn test_identifier_sigils() {
+        let slew = |config| simple_attribute_with::<VerboseError<&str>>(config, "slew : $SLEW;");
+        assert!(slew(ParseConfig::default()).is_err());
+        let expected =
+            GroupItem::SimpleAttr("slew".to_string(), Value::Expression("$SLEW".to_string()));
+        assert_eq!(slew(ParseConfig::lenient()), Ok(("", expected)));
+        let config = ParseConfig {
+            identifier_sigils: "@",
+            ..ParseConfig::default()
+        };
+        assert_eq!(
+            simple_attribute_with::<VerboseError<&str>>(config, "v : @ref * 2;"),
+            Ok((
+                "",
+                GroupItem::SimpleAttr("v".to_string(), Value::Expression("@ref * 2".to_string()))
+            ))
+        );
+        assert!(slew(config).is_err());
+    }
+
+    #[test]
+    fn test_comma_terminator() {
+        let config = ParseConfig {
+            comma_terminator: true,
+            ..ParseConfig::default()
+        };
+        assert_eq!(
+            parse_group_with::<VerboseError<&str>>(
+                config,
+                "cell(AND2) { area : 1, dont_use : true, }"
+            ),
+            Ok((
+                "",
+                GroupItem::Group(
+                    "cell".to_string(),
+                    "AND2".to_string(),
+                    vec![
+                        GroupItem::SimpleAttr("area".to_string(), Value::Float(1.0)),
+                        GroupItem::SimpleAttr("dont_use".to_string(), Value::Bool(true)),
+                    ]
+                )
+            ))
+        );
+        assert!(simple_attribute::<VerboseError<&str>>("area : 1,").is_err());
+    }
+
+    #[test]
+    fn test_group_name_trailing_comma() {
+        let name = |config, input| match parse_group_with::<VerboseError<&str>>(config, input) {
+            Ok((_, GroupItem::Group(_, name, _))) => Some(name),
+            _ => None,
+        };
+        assert_eq!(
+            name(ParseConfig::default(), "pin(A,) { }").as_deref(),
+            Some("A")
+        );
+        assert_eq!(
+            name(ParseConfig::default(), "pin(A, B , ) { }").as_deref(),
+            Some("A,B")
+        );
+        assert_eq!(
+            name(ParseConfig::default(), "pin() { }").as_deref(),
+            Some("")
+        );
+        assert_eq!(name(ParseConfig::default(), "pin(A,,B) { }"), None);
+        assert_eq!(name(ParseConfig::default(), "pin(,) { }"), None);
+
+        let config = ParseConfig {
+            empty_group_names: true,
+            ..ParseConfig::default()
+        };
+        assert_eq!(name(config, "pin(A,,B) { }").as_deref(), Some("A,B"));
+        assert_eq!(name(config, "pin(, A) { }").as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn test_comments_between_values() {
+        let rows = vec![
+            Value::FloatGroup(vec![1.0, 2.0]),
+            Value::FloatGroup(vec![3.0, 4.0]),
+        ];
+        for input in &[
+            "values (\"1, 2\", \\\n /* ss corner */ \"3, 4\");",
+            "values ( /* fast */ \"1, 2\", /* slow */ \\\n \"3, 4\" /* end */ );",
+            "values (\"1, 2\" \\\n /* ss */ \"3, 4\");",
+            "values (\"1, 2\" /* ss */ \"3, 4\");",
+        ] {
+            assert_eq!(
+                complex_attribute::<VerboseError<&str>>(input),
+                Ok((
+                    "",
+                    GroupItem::ComplexAttr("values".to_string(), rows.clone())
+                )),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_indentation_styles() {
+        let rows = vec![
+            Value::FloatGroup(vec![1.0, 2.0]),
+            Value::FloatGroup(vec![3.0, 4.0]),
+            Value::FloatGroup(vec![5.0, 6.0]),
+        ];
+        // `{0}` indents the continuation lines, `{1}` pads before each `\`
+        let layouts = [
+            "values ( \\\n{0}\"1, 2\", \\\n{0}\"3, 4\", \\\n{0}\"5, 6\"{1}\\\n{0});",
+            "values (\"1, 2\",{1}\\\n{0}\"3, 4\",{1}\\\n{0}\"5, 6\");",
+            "values (\"1, 2\"{1}\\\n{0},\"3, 4\"{1}\\\n{0},\"5, 6\");",
+            "values (\"1, 2\"{1}\\\n{0}\"3, 4\"{1}\\\n{0}\"5, 6\");",
+            "values (\"1, 2\",\n{0}\"3, 4\",\n{0}\"5, 6\"\n{0});",
+        ];
+        let indents = ["", "    ", "\t", "\t\t", " \t ", "\t    "];
+        let pads = ["", " ", "\t", " \t"];
+        for layout in layouts.iter() {
+            for indent in indents.iter() {
+                for pad in pads.iter() {
+                    let input = layout.replace("{0}", indent).replace("{1}", pad);
+                    for input in &[
+                        input.clone(),
+                        input.replace('\n', "\r\n"),
+                        // Tools sometimes leave blanks after the `\`
+                        input.replace("\\\n", &format!("\\{}\n", pad)),
+                    ] {
+                        assert_eq!(
+                            complex_attribute::<VerboseError<&str>>(input),
+                            Ok((
+                                "",
+                                GroupItem::ComplexAttr("values".to_string(), rows.clone())
+                            )),
+                            "{:?}",
+                            input
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tabs() {
+        assert_eq!(
+            quoted_floats::<(&str, ErrorKind)>("\"1.0,\t2.0\t\""),
+            Ok(("", vec![1.0, 2.0]))
+        );
+        assert_eq!(
+            complex_attribute::<VerboseError<&str>>("\tindex_1\t(\t\"1.0,\t2.0\",\t3\t);"),
+            Ok((
+                "",
+                GroupItem::ComplexAttr(
+                    "index_1".to_string(),
+                    vec![Value::FloatGroup(vec![1.0, 2.0]), Value::Float(3.0)]
+                )
+            ))
+        );
+        assert_eq!(
+            simple_attribute::<VerboseError<&str>>("comment\t:\t\"a\tb\"\t;"),
+            Ok((
+                "",
+                GroupItem::SimpleAttr("comment".to_string(), Value::String("a\tb".to_string()))
+            ))
+        );
+        assert_eq!(
+            parse_group::<VerboseError<&str>>("cell\t(\tAND2\t)\t{\n\tarea\t:\t1;\n}"),
+            Ok((
+                "",
+                GroupItem::Group(
+                    "cell".to_string(),
+                    "AND2".to_string(),
+                    vec![GroupItem::SimpleAttr("area".to_string(), Value::Float(1.0))]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_trailing_comma_values() {
+        let expected = vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ];
+        for input in &[
+            "( \"a\", \\\n \"b\", \\\n )",
+            "( \"a\", \\\n \"b\" \\\n , )",
+            "(\"a\", \"b\",)",
+        ] {
+            assert_eq!(
+                complex_attribute_values::<VerboseError<&str>>(input),
+                Ok(("", expected.clone())),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            complex_attribute::<VerboseError<&str>>(
+                "values ( \\\n \"1, 2\", \\\n \"3, 4\", \\\n );"
+            ),
+            Ok((
+                "",
+                GroupItem::ComplexAttr(
+                    "values".to_string(),
+                    vec![
+                        Value::FloatGroup(vec![1.0, 2.0]),
+                        Value::FloatGroup(vec![3.0, 4.0]),
+                    ]
+                )
+            ))
+        );
+        assert!(complex_attribute_values::<VerboseError<&str>>("(,)").is_err());
+        assert!(complex_attribute_values::<VerboseError<&str>>("(a,,)").is_err());
+    }
+
+    #[test]
+    fn test_whitespace_separated_values() {
+        assert_eq!(
+            quoted_floats::<(&str, ErrorKind)>(r#""0.1 0.2  0.3""#),
+            Ok(("", vec![0.1, 0.2, 0.3]))
+        );
+        assert_eq!(
+            simple_attr_value::<(&str, ErrorKind)>(r#""0.1 and 0.2";"#),
+            Ok((";", Value::String("0.1 and 0.2".to_string())))
+        );
+        assert_eq!(
+            complex_attribute_values::<VerboseError<&str>>(r#"("0.1 0.2", "0.3 0.4")"#),
+            Ok((
+                "",
+                vec![
+                    Value::FloatGroup(vec![0.1, 0.2]),
+                    Value::FloatGroup(vec![0.3, 0.4]),
+                ]
+            ))
+        );
+        assert_eq!(
+            complex_attribute::<VerboseError<&str>>("foo (1 2 \\\n  3);"),
+            Ok((
+                "",
+                GroupItem::ComplexAttr(
+                    "foo".to_string(),
+                    vec![Value::Float(1.0), Value::Float(2.0), Value::Float(3.0)]
+                )
+            ))
+        );
+        assert_eq!(
+            complex_attribute_values::<VerboseError<&str>>(r#"(a "b c" 1)"#),
+            Ok((
+                "",
+                vec![
+                    Value::Expression("a".to_string()),
+                    Value::String("b c".to_string()),
+                    Value::Float(1.0),
+                ]
+            ))
+        );
+    }
+
     #[test]
     fn test_complex_attr_multi_line() {
         assert_eq!(
@@ -318,6 +1553,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_complex_attr_crlf() {
+        assert_eq!(
+            complex_attribute::<(&str, ErrorKind)>(
+                "values ( \\\r\n    \"0, 0.18\", \\\r\n    \"-0.555, -0.45\" \\\r\n);"
+            ),
+            Ok((
+                "",
+                GroupItem::ComplexAttr(
+                    "values".to_string(),
+                    vec![
+                        Value::FloatGroup(vec![0.0, 0.18]),
+                        Value::FloatGroup(vec![-0.555, -0.45]),
+                    ],
+                )
+            ))
+        );
+    }
+
     #[test]
     fn test_comment() {
         assert_eq!(
@@ -394,6 +1648,56 @@ line
                 GroupItem::SimpleAttr(String::from("attr_name"), Value::Bool(false),)
             ))
         );
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("attr_name : true; "),
+            Ok((
+                " ",
+                GroupItem::SimpleAttr(String::from("attr_name"), Value::Bool(true),)
+            ))
+        );
+    }
+    #[test]
+    fn test_missing_final_semicolon() {
+        assert_eq!(
+            parse_group::<(&str, ErrorKind)>("cell(x){ area : 1 }"),
+            parse_group::<(&str, ErrorKind)>("cell(x){ area : 1; }"),
+        );
+        assert_eq!(
+            parse_group::<(&str, ErrorKind)>("cell(x){ area : 1}"),
+            Ok((
+                "",
+                GroupItem::Group(
+                    "cell".to_string(),
+                    "x".to_string(),
+                    vec![GroupItem::SimpleAttr("area".to_string(), Value::Float(1.0))],
+                )
+            ))
+        );
+        assert!(parse_group::<(&str, ErrorKind)>("cell(x){ area : 1\n footprint : x; }").is_err());
+    }
+
+    #[test]
+    fn test_simple_attribute_bool_prefix() {
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("attr_name : truest ; "),
+            Ok((
+                " ",
+                GroupItem::SimpleAttr(
+                    String::from("attr_name"),
+                    Value::Expression(String::from("truest")),
+                )
+            ))
+        );
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("attr_name : true_val ; "),
+            Ok((
+                " ",
+                GroupItem::SimpleAttr(
+                    String::from("attr_name"),
+                    Value::Expression(String::from("true_val")),
+                )
+            ))
+        );
     }
     #[test]
     fn test_simple_attribute_float() {
@@ -430,6 +1734,29 @@ line
         );
     }
 
+    #[test]
+    fn test_simple_attribute_radix_int() {
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("attr_name : 0xFF ; "),
+            Ok((
+                " ",
+                GroupItem::SimpleAttr(String::from("attr_name"), Value::Float(255.0),)
+            ))
+        );
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("attr_name : 0b1010 ; "),
+            Ok((
+                " ",
+                GroupItem::SimpleAttr(String::from("attr_name"), Value::Float(10.0),)
+            ))
+        );
+        assert_eq!(
+            radix_integer::<(&str, ErrorKind)>("0xGG"),
+            Err(Err::Error(("0xGG", ErrorKind::Tag)))
+        );
+        assert!(simple_attribute::<(&str, ErrorKind)>("attr_name : 0xGG ; ").is_err());
+    }
+
     #[test]
     fn test_expression() {
         let expressions = vec![
@@ -501,6 +1828,28 @@ line
         );
     }
 
+    #[test]
+    fn test_simple_attribute_identifier() {
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("related_power_pin : VDD;\n"),
+            Ok((
+                "\n",
+                GroupItem::SimpleAttr(
+                    String::from("related_power_pin"),
+                    Value::Expression(String::from("VDD")),
+                )
+            ))
+        );
+        assert_eq!(
+            simple_attribute::<(&str, ErrorKind)>("when : \"A&B\";\n"),
+            Ok((
+                "\n",
+                GroupItem::SimpleAttr(String::from("when"), Value::String(String::from("A&B")))
+            ))
+        );
+        assert!(simple_attribute::<(&str, ErrorKind)>("related_power_pin : ;").is_err());
+    }
+
     #[test]
     fn test_simple_attribute_string() {
         assert_eq!(
@@ -515,6 +1864,85 @@ line
         );
     }
 
+    #[test]
+    fn test_statetable_continuation() {
+        let data = "statetable ( \"CP D\", \"IQ IQN\" ) {
+            table : \"R L : - - : L H, \\
+                     R H : - - : H L, \\\r
+                     ~R - : - - : N N\" ;
+        }";
+        assert_eq!(
+            parse_group::<(&str, ErrorKind)>(data),
+            Ok((
+                "",
+                GroupItem::Group(
+                    "statetable".to_string(),
+                    "CP D,IQ IQN".to_string(),
+                    vec![GroupItem::SimpleAttr(
+                        "table".to_string(),
+                        Value::String(
+                            "R L : - - : L H, R H : - - : H L, ~R - : - - : N N".to_string()
+                        ),
+                    )],
+                ),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_wrapped_function() {
+        let data = "function : \"(A1 & A2) | \\\n\t\t   (B1 &  B2) | \\\r\n     (C1 & C2) \";";
+        let joined = "(A1 & A2) | (B1 &  B2) | (C1 & C2) ";
+        let function = |item| match item {
+            Ok((_, GroupItem::SimpleAttr(_, Value::String(s)))) => s,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(
+            function(simple_attribute::<VerboseError<&str>>(data)),
+            joined
+        );
+
+        let config = ParseConfig {
+            normalize_functions: true,
+            ..ParseConfig::default()
+        };
+        let normalized = function(simple_attribute_with::<VerboseError<&str>>(config, data));
+        assert_eq!(normalized, "(A1 & A2) | (B1 & B2) | (C1 & C2)");
+        let expr = crate::function::parse_function(&normalized).unwrap();
+        assert_eq!(expr.variables(), ["A1", "A2", "B1", "B2", "C1", "C2"]);
+
+        // Other attributes are left as written
+        let table = simple_attribute_with::<VerboseError<&str>>(config, "table : \"A  B\";");
+        assert_eq!(function(table), "A  B");
+    }
+
+    #[test]
+    fn test_group_bus_names() {
+        assert_eq!(
+            group_header::<(&str, ErrorKind)>("pin(A[3]) {"),
+            Ok(("", ("pin", Cow::Borrowed("A[3]"))))
+        );
+        assert_eq!(
+            group_header::<(&str, ErrorKind)>("bus(D[7:0]) {"),
+            Ok(("", ("bus", Cow::Borrowed("D[7:0]"))))
+        );
+        assert!(group_header::<(&str, ErrorKind)>("pin(A[x]) {").is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_split_libraries() {
+        let input = "/* a { */ library(a) { s : \"}\"; cell(x) {} }\nlibrary(b) {}\n/* end */";
+        assert_eq!(
+            split_libraries(input),
+            vec![
+                "/* a { */ library(a) { s : \"}\"; cell(x) {} }",
+                "\nlibrary(b) {}",
+                "\n/* end */",
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_group() {
         let data = "library ( foo ) {
@@ -643,5 +2071,4 @@ library(foo) {
             ))
         );
     }
-
 }
@@ -0,0 +1,105 @@
+//! Multi-voltage `power_supply` declarations
+//!
+//! ```text
+//! power_supply() {
+//!     default_power_rail : VDD;
+//!     power_rail (VDD, 0.9);
+//!     power_rail (VDDL, 0.7);
+//! }
+//! ```
+//!
+//! Each rail is a repeated `power_rail` complex attribute. [`Group`](crate::liberty::Group)
+//! keeps only the last attribute of each name, so [`PowerSupply`] is read from the AST.
+
+use std::collections::HashMap;
+
+use crate::ast::GroupItem;
+
+/// Rails declared by a `power_supply` group
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PowerSupply {
+    /// Name of the rail set by `default_power_rail`
+    pub default_power_rail: Option<String>,
+    /// Voltage of each rail, by rail name
+    pub rails: HashMap<String, f64>,
+}
+
+impl PowerSupply {
+    /// Read the rails of a `power_supply` group
+    ///
+    /// Returns `None` if the item isn't a `power_supply` group. `power_rail` attributes that
+    /// aren't a name followed by a voltage are skipped.
+    ///
+    /// This reads the AST, not a [`Group`](crate::liberty::Group): a `Group` keeps only the last
+    /// `power_rail`, so the others are already lost once a file is parsed with
+    /// [`parse_lib`](crate::parse_lib). Parse with
+    /// [`LibertyAst::from_string`](crate::ast::LibertyAst::from_string) to get the items:
+    ///
+    /// ```
+    /// use liberty_parse::{ast::LibertyAst, power::PowerSupply};
+    ///
+    /// let ast = LibertyAst::from_string(
+    ///     "library(foo) { power_supply() { power_rail(VDD, 0.9); power_rail(VDDL, 0.7); } }",
+    /// )
+    /// .unwrap();
+    /// let supply = PowerSupply::from_group_item(&ast.0[0].group().2[0]).unwrap();
+    /// assert_eq!(supply.rails.len(), 2);
+    /// ```
+    pub fn from_group_item(group_item: &GroupItem) -> Option<Self> {
+        let items = match group_item {
+            GroupItem::Group(type_, _, items) if type_ == "power_supply" => items,
+            _ => return None,
+        };
+        let mut supply = PowerSupply::default();
+        for item in items {
+            match item {
+                GroupItem::SimpleAttr(name, value) if name == "default_power_rail" => {
+                    supply.default_power_rail = value.as_text().map(String::from);
+                }
+                GroupItem::ComplexAttr(name, values) if name == "power_rail" => {
+                    if let [rail, voltage] = values.as_slice() {
+                        if let (Some(rail), Some(voltage)) = (rail.as_text(), voltage.as_float()) {
+                            supply.rails.insert(rail.to_string(), voltage);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(supply)
+    }
+
+    /// Voltage of the default rail, if it's declared
+    pub fn default_voltage(&self) -> Option<f64> {
+        self.rails
+            .get(self.default_power_rail.as_ref()?.as_str())
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::LibertyAst;
+
+    #[test]
+    fn test_power_supply() {
+        let ast = LibertyAst::from_string(
+            "library(foo) {
+                power_supply() {
+                    default_power_rail : VDD;
+                    power_rail (VDD, 0.9);
+                    power_rail (VDDL, 0.7);
+                }
+            }",
+        )
+        .unwrap();
+        let (_, _, items) = ast.0[0].group();
+        let supply = PowerSupply::from_group_item(&items[0]).unwrap();
+        assert_eq!(supply.default_power_rail.as_deref(), Some("VDD"));
+        assert_eq!(supply.rails.len(), 2);
+        assert_eq!(supply.rails["VDDL"], 0.7);
+        assert_eq!(supply.default_voltage(), Some(0.9));
+        assert_eq!(PowerSupply::from_group_item(&ast.0[0]), None);
+    }
+}
@@ -0,0 +1,899 @@
+//! Lookup tables used by timing and power models
+//!
+//! Table groups such as `cell_rise` or `receiver_capacitance1_rise` store their axes in the
+//! `index_1` and `index_2` complex attributes and their data in `values`, with one quoted row
+//! per `index_1` entry:
+//!
+//! ```text
+//! cell_rise(delay_template_2x3) {
+//!     index_1 ("0.5, 1.0");
+//!     index_2 ("10.0, 20.0, 30.0");
+//!     values ("0.1, 0.2, 0.3", \
+//!             "0.11, 0.21, 0.31");
+//! }
+//! ```
+//!
+//! Three-dimensional tables add an `index_3` and are read as a [`LookupTable3d`].
+
+use std::{
+    cell::OnceCell,
+    collections::{BTreeMap, HashMap},
+    error, fmt,
+};
+
+use crate::ast::Value;
+use crate::liberty::{FromGroup, Group, Liberty, Library, Pin, ToGroup};
+
+/// Two-dimensional lookup table
+///
+/// One-dimensional tables have an empty `index_2` and a single row of `values`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LookupTable {
+    pub index_1: Vec<f64>,
+    pub index_2: Vec<f64>,
+    /// Rows of values, one per `index_1` entry
+    pub values: Vec<Vec<f64>>,
+}
+
+impl LookupTable {
+    /// Read a lookup table from a table group
+    ///
+    /// Returns `None` if the group has no `values` attribute, it isn't numeric, or the group
+    /// has an `index_3`, in which case use [`LookupTable3d`]. Missing indices are left empty.
+    pub fn from_group(group: &Group) -> Option<Self> {
+        if group.complex_attributes.contains_key("index_3") {
+            return None;
+        }
+        let values = rows(&group.complex_attributes)?;
+        Some(LookupTable {
+            index_1: index(&group.complex_attributes, "index_1")?,
+            index_2: index(&group.complex_attributes, "index_2")?,
+            values,
+        })
+    }
+
+    /// Convert the table into a table group of type `type_`, such as `cell_rise`
+    ///
+    /// Each index becomes a single quoted float group and each row of `values` becomes its
+    /// own quoted float group. An empty `index_2` is omitted.
+    pub fn to_group(&self, type_: &str) -> Group {
+        let mut group = Group::new(type_, "");
+        group.complex_attributes.insert(
+            "index_1".to_string(),
+            vec![Value::FloatGroup(self.index_1.clone())],
+        );
+        if !self.index_2.is_empty() {
+            group.complex_attributes.insert(
+                "index_2".to_string(),
+                vec![Value::FloatGroup(self.index_2.clone())],
+            );
+        }
+        group.complex_attributes.insert(
+            "values".to_string(),
+            self.values.iter().cloned().map(Value::FloatGroup).collect(),
+        );
+        group
+    }
+
+    /// Convert the table into its `index_1`, `index_2`, and `values` complex attributes,
+    /// checking that `values` is rectangular
+    ///
+    /// A two-dimensional table needs one row per `index_1` entry, each with one value per
+    /// `index_2` entry. A one-dimensional table, with an empty `index_2`, needs a single row
+    /// with one value per `index_1` entry. An empty `index_2` is omitted, as in
+    /// [`to_group`](LookupTable::to_group).
+    pub fn into_complex_attrs(self) -> Result<Vec<(String, Vec<Value>)>, TableShapeError> {
+        self.check_shape()?;
+        let mut attrs = vec![("index_1".to_string(), vec![Value::FloatGroup(self.index_1)])];
+        if !self.index_2.is_empty() {
+            attrs.push(("index_2".to_string(), vec![Value::FloatGroup(self.index_2)]));
+        }
+        attrs.push((
+            "values".to_string(),
+            self.values.into_iter().map(Value::FloatGroup).collect(),
+        ));
+        Ok(attrs)
+    }
+
+    // Check that `values` has the shape `into_complex_attrs` describes
+    fn check_shape(&self) -> Result<(), TableShapeError> {
+        let (rows, columns) = if self.index_2.is_empty() {
+            (1, self.index_1.len())
+        } else {
+            (self.index_1.len(), self.index_2.len())
+        };
+        if self.values.len() != rows {
+            return Err(TableShapeError::Rows {
+                expected: rows,
+                found: self.values.len(),
+            });
+        }
+        if let Some((row, values)) = self
+            .values
+            .iter()
+            .enumerate()
+            .find(|(_, values)| values.len() != columns)
+        {
+            return Err(TableShapeError::Columns {
+                row,
+                expected: columns,
+                found: values.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Swap `index_1` and `index_2`, transposing `values` to match
+    ///
+    /// One-dimensional tables are returned unchanged. Fails, rather than dropping values, if
+    /// `values` doesn't match the indices as described in
+    /// [`into_complex_attrs`](LookupTable::into_complex_attrs).
+    pub fn transpose(&self) -> Result<LookupTable, TableShapeError> {
+        self.check_shape()?;
+        if self.index_2.is_empty() {
+            return Ok(self.clone());
+        }
+        Ok(LookupTable {
+            index_1: self.index_2.clone(),
+            index_2: self.index_1.clone(),
+            values: (0..self.index_2.len())
+                .map(|col| self.values.iter().map(|row| row[col]).collect())
+                .collect(),
+        })
+    }
+}
+
+/// Mismatch between the indices and `values` of a [`LookupTable`], found by
+/// [`LookupTable::into_complex_attrs`] and [`LookupTable::transpose`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableShapeError {
+    /// `values` has the wrong number of rows
+    Rows { expected: usize, found: usize },
+    /// A row of `values`, counting from 0, has the wrong number of entries
+    Columns {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for TableShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableShapeError::Rows { expected, found } => {
+                write!(f, "expected {} rows of values, found {}", expected, found)
+            }
+            TableShapeError::Columns {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} values in row {}, found {}",
+                expected, row, found
+            ),
+        }
+    }
+}
+
+impl error::Error for TableShapeError {}
+
+/// Table group whose [`LookupTable`] is read on first use and then reused
+///
+/// The group is borrowed for as long as the cache lives, so it can't change under the cached
+/// table. Drop the cache to edit the group, then create a new one.
+///
+/// The cache is kept outside [`Group`] rather than in a field filled by
+/// [`Group::as_lookup_table`]: the group's attribute maps are public fields, so edits made
+/// through them couldn't invalidate a cache stored in the group, and it would go stale.
+#[derive(Debug)]
+pub struct CachedTable<'a> {
+    group: &'a Group,
+    table: OnceCell<Option<LookupTable>>,
+}
+
+impl<'a> CachedTable<'a> {
+    pub fn new(group: &'a Group) -> Self {
+        CachedTable {
+            group,
+            table: OnceCell::new(),
+        }
+    }
+
+    /// The table group being read
+    pub fn group(&self) -> &'a Group {
+        self.group
+    }
+
+    /// Get the lookup table, reading it from the group on the first call
+    pub fn get(&self) -> Option<&LookupTable> {
+        self.table
+            .get_or_init(|| LookupTable::from_group(self.group))
+            .as_ref()
+    }
+}
+
+/// Timing arc read from a pin's `timing` group
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimingArc {
+    /// Pin the arc starts from, or empty if `related_pin` is missing
+    pub related_pin: String,
+    pub timing_type: Option<String>,
+    pub timing_sense: Option<String>,
+    pub cell_rise: Option<LookupTable>,
+    pub cell_fall: Option<LookupTable>,
+    pub rise_transition: Option<LookupTable>,
+    pub fall_transition: Option<LookupTable>,
+}
+
+impl TimingArc {
+    /// Read a timing arc from a `timing` group
+    pub fn from_group(timing: &Group) -> Self {
+        let text = |name: &str| {
+            timing
+                .simple_attributes
+                .get(name)
+                .and_then(Value::as_text)
+                .map(String::from)
+        };
+        let table = |type_: &str| {
+            timing
+                .iter_subgroups_of_type(type_)
+                .find_map(LookupTable::from_group)
+        };
+        TimingArc {
+            related_pin: text("related_pin").unwrap_or_default(),
+            timing_type: text("timing_type"),
+            timing_sense: text("timing_sense"),
+            cell_rise: table("cell_rise"),
+            cell_fall: table("cell_fall"),
+            rise_transition: table("rise_transition"),
+            fall_transition: table("fall_transition"),
+        }
+    }
+}
+
+/// Three-dimensional lookup table
+///
+/// `values` is stored as one quoted row per `index_1` and `index_2` pair, ordered by `index_1`
+/// then `index_2`, with one entry per `index_3` value in each row.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LookupTable3d {
+    pub index_1: Vec<f64>,
+    pub index_2: Vec<f64>,
+    pub index_3: Vec<f64>,
+    /// Values indexed by `index_1`, `index_2`, then `index_3` entry
+    pub values: Vec<Vec<Vec<f64>>>,
+}
+
+impl LookupTable3d {
+    /// Read a three-dimensional lookup table from a table group
+    ///
+    /// Returns `None` if the group has no `index_3`, or `values` isn't numeric or doesn't
+    /// match the shape of the indices.
+    pub fn from_group(group: &Group) -> Option<Self> {
+        let index_1 = index(&group.complex_attributes, "index_1")?;
+        let index_2 = index(&group.complex_attributes, "index_2")?;
+        let index_3 = index(&group.complex_attributes, "index_3")?;
+        if index_3.is_empty() {
+            return None;
+        }
+        let rows = rows(&group.complex_attributes)?;
+        if rows.len() != index_1.len() * index_2.len()
+            || rows.iter().any(|row| row.len() != index_3.len())
+        {
+            return None;
+        }
+        let values = if index_2.is_empty() {
+            vec![]
+        } else {
+            rows.chunks(index_2.len()).map(<[_]>::to_vec).collect()
+        };
+        Some(LookupTable3d {
+            index_1,
+            index_2,
+            index_3,
+            values,
+        })
+    }
+
+    /// Convert the table into a table group of type `type_`
+    pub fn to_group(&self, type_: &str) -> Group {
+        let mut group = Group::new(type_, "");
+        for (name, index) in &[
+            ("index_1", &self.index_1),
+            ("index_2", &self.index_2),
+            ("index_3", &self.index_3),
+        ] {
+            group
+                .complex_attributes
+                .insert(name.to_string(), vec![Value::FloatGroup(index.to_vec())]);
+        }
+        group.complex_attributes.insert(
+            "values".to_string(),
+            self.values
+                .iter()
+                .flatten()
+                .cloned()
+                .map(Value::FloatGroup)
+                .collect(),
+        );
+        group
+    }
+}
+
+// Read the quoted rows of `values`
+pub(crate) fn rows(attributes: &HashMap<String, Vec<Value>>) -> Option<Vec<Vec<f64>>> {
+    attributes
+        .get("values")?
+        .iter()
+        .map(|v| match v {
+            Value::FloatGroup(row) => Some(row.clone()),
+            Value::Float(v) => Some(vec![*v]),
+            _ => None,
+        })
+        .collect()
+}
+
+// Read an index attribute, treating a missing one as empty
+pub(crate) fn index(attributes: &HashMap<String, Vec<Value>>, name: &str) -> Option<Vec<f64>> {
+    match attributes.get(name) {
+        None => Some(vec![]),
+        Some(values) => values.iter().try_fold(vec![], |mut acc, v| {
+            match v {
+                Value::FloatGroup(vs) => acc.extend(vs),
+                Value::Float(v) => acc.push(*v),
+                _ => return None,
+            }
+            Some(acc)
+        }),
+    }
+}
+
+// Find a table nested in a `receiver_capacitance` group
+fn receiver_capacitance(groups: &[Group], name: &str) -> Option<LookupTable> {
+    groups
+        .iter()
+        .filter(|g| g.type_ == "receiver_capacitance")
+        .flat_map(|g| g.iter_subgroups_of_type(name))
+        .find_map(LookupTable::from_group)
+}
+
+fn timing_arcs(groups: &[Group]) -> Vec<TimingArc> {
+    groups
+        .iter()
+        .filter(|g| g.type_ == "timing")
+        .map(TimingArc::from_group)
+        .collect()
+}
+
+// Count the table groups, at any depth, that reference one of the templates in `usage`
+fn count_template_references(groups: &[Group], usage: &mut BTreeMap<String, usize>) {
+    for group in groups {
+        if let Some(count) = usage.get_mut(&group.name) {
+            *count += 1;
+        }
+        count_template_references(&group.groups, usage);
+    }
+}
+
+impl Liberty {
+    /// Count the references to each table template across all libraries
+    ///
+    /// Templates are the library-level groups whose type ends in `_template`, such as
+    /// `lu_table_template` and `power_lut_template`. A reference is any group within a cell
+    /// named after a template, such as `cell_rise(delay_template)`. Unreferenced templates
+    /// have a count of zero. Templates are in name order.
+    pub fn template_usage(&self) -> BTreeMap<String, usize> {
+        let mut usage: BTreeMap<String, usize> = self
+            .0
+            .iter()
+            .flat_map(|lib| lib.groups.iter())
+            .filter(|g| g.type_.ends_with("_template"))
+            .map(|g| (g.name.clone(), 0))
+            .collect();
+        for cell in self.0.iter().flat_map(|lib| lib.cells.values()) {
+            count_template_references(&cell.groups, &mut usage);
+            for pin in cell.pins.values() {
+                count_template_references(&pin.groups, &mut usage);
+            }
+        }
+        usage
+    }
+}
+
+// Fill the `timing` groups nested in `group` from the `default_timing` groups in scope,
+// innermost first
+fn resolve_timing_defaults(group: &mut Group, defaults: &mut Vec<Group>) {
+    let scoped = defaults.len();
+    defaults.extend(group.iter_subgroups_of_type("default_timing").cloned());
+    for subgroup in group.groups.iter_mut() {
+        if subgroup.type_ == "timing" {
+            for default in defaults.iter().rev() {
+                inherit(subgroup, default);
+            }
+        }
+        if subgroup.type_ != "default_timing" {
+            resolve_timing_defaults(subgroup, defaults);
+        }
+    }
+    defaults.truncate(scoped);
+}
+
+// Copy the attributes, and sub-groups of new types, that `group` doesn't set from `default`
+fn inherit(group: &mut Group, default: &Group) {
+    for (name, value) in &default.simple_attributes {
+        group
+            .simple_attributes
+            .entry(name.clone())
+            .or_insert_with(|| value.clone());
+    }
+    for (name, values) in &default.complex_attributes {
+        group
+            .complex_attributes
+            .entry(name.clone())
+            .or_insert_with(|| values.clone());
+    }
+    for subgroup in &default.groups {
+        if !group.groups.iter().any(|g| g.type_ == subgroup.type_) {
+            group.groups.push(subgroup.clone());
+        }
+    }
+}
+
+// Complex attributes holding the axes and data of a table
+const TABLE_ATTRIBUTES: [&str; 4] = ["index_1", "index_2", "index_3", "values"];
+
+impl Liberty {
+    /// Remove the `index_*` and `values` attributes from every group, at any depth of any
+    /// library
+    ///
+    /// Table groups such as `cell_rise` and their other attributes are kept, leaving only the
+    /// structure of the library. Useful for comparing which cells, pins, and arcs exist without
+    /// the bulk of the numeric data.
+    pub fn strip_tables(&mut self) {
+        for lib in self.0.iter_mut() {
+            lib.walk_attributes_mut(&mut |_, complex| {
+                for name in TABLE_ATTRIBUTES.iter() {
+                    complex.remove(*name);
+                }
+            });
+        }
+    }
+
+    /// Copy of the structure with `default_timing` values filled into each `timing` group
+    ///
+    /// A `default_timing` group applies to the `timing` groups nested anywhere in the group
+    /// that declares it, so a cell's defaults take precedence over the library's. Attributes
+    /// and table types set in a `timing` group itself are never replaced.
+    pub fn resolve_timing_defaults(&self) -> Liberty {
+        Liberty(
+            self.0
+                .iter()
+                .map(|lib| {
+                    let mut group = lib.clone().into_group();
+                    resolve_timing_defaults(&mut group, &mut vec![]);
+                    Library::from_group(group)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Group {
+    /// Read this group as a [`LookupTable`]
+    pub fn as_lookup_table(&self) -> Option<LookupTable> {
+        LookupTable::from_group(self)
+    }
+
+    /// Read this group as a [`LookupTable`] once, for repeated lookups
+    ///
+    /// [`Group::as_lookup_table`] itself doesn't cache, see [`CachedTable`] for why.
+    pub fn cached_lookup_table(&self) -> CachedTable<'_> {
+        CachedTable::new(self)
+    }
+
+    /// Read this group as a [`LookupTable3d`]
+    pub fn as_lookup_table_3d(&self) -> Option<LookupTable3d> {
+        LookupTable3d::from_group(self)
+    }
+
+    /// Transpose this table group in place, see [`LookupTable::transpose`]
+    ///
+    /// Returns `Ok(false)` if it isn't a lookup table. The group is left unchanged unless this
+    /// returns `Ok(true)`.
+    pub fn transpose_table(&mut self) -> Result<bool, TableShapeError> {
+        let table = match self.as_lookup_table() {
+            Some(table) => table.transpose()?,
+            None => return Ok(false),
+        };
+        let transposed = table.to_group(&self.type_);
+        for name in &["index_1", "index_2", "values"] {
+            match transposed.complex_attributes.get(*name) {
+                Some(values) => self
+                    .complex_attributes
+                    .insert(name.to_string(), values.clone()),
+                None => self.complex_attributes.remove(*name),
+            };
+        }
+        Ok(true)
+    }
+
+    /// Get a CCS receiver capacitance table, such as `receiver_capacitance1_rise`, from a
+    /// `timing` group
+    pub fn receiver_capacitance(&self, name: &str) -> Option<LookupTable> {
+        receiver_capacitance(&self.groups, name)
+    }
+
+    /// Read the timing arcs of a pin group, one per `timing` sub-group
+    pub fn timing_arcs(&self) -> Vec<TimingArc> {
+        timing_arcs(&self.groups)
+    }
+}
+
+impl Pin {
+    /// Read the timing arcs of the pin, one per `timing` sub-group
+    pub fn timing_arcs(&self) -> Vec<TimingArc> {
+        timing_arcs(&self.groups)
+    }
+
+    /// Get a CCS receiver capacitance table, such as `receiver_capacitance1_rise`, declared
+    /// directly on the pin
+    pub fn receiver_capacitance(&self, name: &str) -> Option<LookupTable> {
+        receiver_capacitance(&self.groups, name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::LibertyAst;
+
+    #[test]
+    fn test_from_group() {
+        let lib = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let table = lib
+            .into_iter()
+            .next()
+            .and_then(|lib| lib.cells.get("AND2").cloned())
+            .and_then(|cell| cell.pins.get("o").cloned())
+            .and_then(|pin| pin.groups.into_iter().find(|g| g.type_ == "timing"))
+            .and_then(|timing| {
+                timing
+                    .iter_subgroups_of_type("cell_rise")
+                    .next()
+                    .and_then(Group::as_lookup_table)
+            })
+            .unwrap();
+        assert_eq!(table.index_1.len(), table.values.len());
+        assert!(table
+            .values
+            .iter()
+            .all(|row| row.len() == table.index_2.len()));
+    }
+
+    #[test]
+    fn test_strip_tables() {
+        let mut liberty = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let cell_count = liberty.cell_count();
+        liberty.strip_tables();
+        assert_eq!(liberty.cell_count(), cell_count);
+
+        let pin = &liberty.0[0].cells["AND2"].pins["o"];
+        let timing = pin.groups.iter().find(|g| g.type_ == "timing").unwrap();
+        assert!(timing.simple_attributes.contains_key("related_pin"));
+        let cell_rise = timing.iter_subgroups_of_type("cell_rise").next().unwrap();
+        assert!(cell_rise.complex_attributes.is_empty());
+        assert_eq!(cell_rise.as_lookup_table(), None);
+        assert!(!liberty.to_string().contains("values"));
+    }
+
+    #[test]
+    fn test_into_complex_attrs() {
+        let table = LookupTable {
+            index_1: vec![0.1, 0.2, 0.3],
+            index_2: vec![1.0, 2.0, 3.0],
+            values: vec![
+                vec![1.0, 2.0, 3.0],
+                vec![4.0, 5.0, 6.0],
+                vec![7.0, 8.0, 9.0],
+            ],
+        };
+        let attrs = table.clone().into_complex_attrs().unwrap();
+        let names: Vec<&str> = attrs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["index_1", "index_2", "values"]);
+        let values = &attrs[2].1;
+        assert_eq!(values.len(), 3);
+        assert!(values
+            .iter()
+            .all(|v| matches!(v, Value::FloatGroup(row) if row.len() == 3)));
+
+        let mut group = Group::new("cell_rise", "");
+        group.complex_attributes.extend(attrs);
+        assert_eq!(group.as_lookup_table(), Some(table.clone()));
+
+        let mut ragged = table.clone();
+        ragged.values[1].pop();
+        assert_eq!(
+            ragged.into_complex_attrs(),
+            Err(TableShapeError::Columns {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+        let mut short = table;
+        short.values.pop();
+        assert_eq!(
+            short.into_complex_attrs(),
+            Err(TableShapeError::Rows {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_timing_arcs() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) {
+                    pin(o) {
+                        timing() {
+                            related_pin : "a";
+                            timing_sense : positive_unate;
+                            cell_rise(delay_template_2x3) {
+                                index_1 ("0.5, 1.0");
+                                index_2 ("10.0, 20.0, 30.0");
+                                values ("0.1, 0.2, 0.3", \
+                                        "0.11, 0.21, 0.31");
+                            }
+                        }
+                        timing() { related_pin : "b"; timing_type : combinational; }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let pin = &liberty["foo"].cells["AND2"].pins["o"];
+        let arcs = pin.timing_arcs();
+        assert_eq!(arcs.len(), 2);
+        assert_eq!(arcs[0].related_pin, "a");
+        assert_eq!(arcs[0].timing_sense.as_deref(), Some("positive_unate"));
+        assert_eq!(arcs[0].timing_type, None);
+        let cell_rise = arcs[0].cell_rise.as_ref().unwrap();
+        assert_eq!(cell_rise.index_2, vec![10.0, 20.0, 30.0]);
+        assert_eq!(cell_rise.values[1], vec![0.11, 0.21, 0.31]);
+        assert_eq!(arcs[0].cell_fall, None);
+        assert_eq!(arcs[1].related_pin, "b");
+        assert_eq!(arcs[1].timing_type.as_deref(), Some("combinational"));
+        assert_eq!(pin.clone().into_group().timing_arcs(), arcs);
+    }
+
+    #[test]
+    fn test_resolve_timing_defaults() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                default_timing() { timing_type : combinational; timing_sense : positive_unate; }
+                cell(AND2) {
+                    default_timing() { timing_sense : non_unate; }
+                    pin(o) {
+                        timing() { related_pin : "a"; }
+                        timing() { related_pin : "b"; timing_type : rising_edge; }
+                    }
+                }
+                cell(INV) { pin(o) { timing() { related_pin : "a"; } } }
+            }"#,
+        )
+        .unwrap();
+        let resolved = liberty.resolve_timing_defaults();
+        let arcs = resolved["foo"].cells["AND2"].pins["o"].timing_arcs();
+        assert_eq!(arcs[0].timing_type.as_deref(), Some("combinational"));
+        assert_eq!(arcs[0].timing_sense.as_deref(), Some("non_unate"));
+        assert_eq!(arcs[1].timing_type.as_deref(), Some("rising_edge"));
+        let inv = resolved["foo"].cells["INV"].pins["o"].timing_arcs();
+        assert_eq!(inv[0].timing_sense.as_deref(), Some("positive_unate"));
+
+        let original = liberty["foo"].cells["AND2"].pins["o"].timing_arcs();
+        assert_eq!(original[0].timing_type, None);
+        assert_eq!(resolved["foo"].groups, liberty["foo"].groups);
+    }
+
+    #[test]
+    fn test_lookup_table_3d() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(DFF) {
+                    pin(q) {
+                        timing() {
+                            cell_rise(delay_template_2x2x2) {
+                                index_1 ("0.1, 0.2");
+                                index_2 ("1, 2");
+                                index_3 ("10, 20");
+                                values ("1, 2", "3, 4", \
+                                        "5, 6", "7, 8");
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let timing = &liberty["foo"].cells["DFF"].pins["q"].groups[0];
+        let group = timing.iter_subgroups_of_type("cell_rise").next().unwrap();
+        assert_eq!(group.as_lookup_table(), None);
+        let table = group.as_lookup_table_3d().unwrap();
+        assert_eq!(table.index_3, vec![10.0, 20.0]);
+        assert_eq!(
+            table.values,
+            vec![
+                vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                vec![vec![5.0, 6.0], vec![7.0, 8.0]],
+            ]
+        );
+        assert_eq!(table.values[1][0][1], 6.0);
+        assert_eq!(
+            table.to_group("cell_rise").as_lookup_table_3d(),
+            Some(table)
+        );
+
+        let mut short = group.clone();
+        short.complex_attributes.get_mut("values").unwrap().pop();
+        assert_eq!(short.as_lookup_table_3d(), None);
+        let table_2d = LookupTable {
+            index_1: vec![0.1, 0.2],
+            index_2: vec![1.0, 2.0],
+            values: vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+        };
+        assert_eq!(table_2d.to_group("cell_rise").as_lookup_table_3d(), None);
+    }
+
+    #[test]
+    fn test_cached_table() {
+        let table = LookupTable {
+            index_1: vec![0.5, 1.0],
+            index_2: vec![10.0],
+            values: vec![vec![0.1], vec![0.2]],
+        };
+        let mut group = table.to_group("cell_rise");
+        let cached = group.cached_lookup_table();
+        assert_eq!(cached.get(), Some(&table));
+        assert!(std::ptr::eq(cached.get().unwrap(), cached.get().unwrap()));
+        assert!(std::ptr::eq(cached.group(), &group));
+
+        group.complex_attributes.remove("values");
+        assert_eq!(group.cached_lookup_table().get(), None);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let table = LookupTable {
+            index_1: vec![0.5, 1.0],
+            index_2: vec![10.0, 20.0, 30.0],
+            values: vec![vec![0.1, 0.2, 0.3], vec![0.11, 0.21, 0.31]],
+        };
+        let transposed = table.transpose().unwrap();
+        assert_eq!(transposed.index_1, table.index_2);
+        assert_eq!(transposed.index_2, table.index_1);
+        assert_eq!(transposed.values.len(), 3);
+        for (i, row) in table.values.iter().enumerate() {
+            for (j, v) in row.iter().enumerate() {
+                assert_eq!(transposed.values[j][i], *v);
+            }
+        }
+        assert_eq!(transposed.transpose(), Ok(table.clone()));
+
+        let mut group = table.to_group("cell_rise");
+        assert_eq!(group.transpose_table(), Ok(true));
+        assert_eq!(group.as_lookup_table(), Some(transposed));
+        assert_eq!(Group::new("timing", "").transpose_table(), Ok(false));
+
+        let mut ragged = table;
+        ragged.values[1].pop();
+        let error = TableShapeError::Columns {
+            row: 1,
+            expected: 3,
+            found: 2,
+        };
+        assert_eq!(ragged.transpose(), Err(error.clone()));
+        let mut group = ragged.to_group("cell_rise");
+        let original = group.clone();
+        assert_eq!(group.transpose_table(), Err(error));
+        assert_eq!(group, original);
+    }
+
+    #[test]
+    fn test_template_usage() {
+        let lib = crate::parse_lib(
+            r#"library(foo) {
+                lu_table_template(delay_3x3) { variable_1 : input_net_transition; }
+                power_lut_template(power_3x3) { variable_1 : input_transition_time; }
+                lu_table_template(unused) { variable_1 : input_net_transition; }
+                cell(AND2) {
+                    pin(o) {
+                        timing() {
+                            cell_rise(delay_3x3) { values ("1"); }
+                            cell_fall(delay_3x3) { values ("1"); }
+                        }
+                        internal_power() {
+                            rise_power(power_3x3) { values ("1"); }
+                        }
+                    }
+                }
+                cell(INV) {
+                    pin(o) {
+                        timing() { cell_rise(delay_3x3) { values ("1"); } }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let usage = lib.template_usage();
+        let names: Vec<&str> = usage.keys().map(String::as_str).collect();
+        assert_eq!(names, ["delay_3x3", "power_3x3", "unused"]);
+        assert_eq!(usage["delay_3x3"], 3);
+        assert_eq!(usage["power_3x3"], 1);
+        assert_eq!(usage["unused"], 0);
+    }
+
+    #[test]
+    fn test_to_group_round_trip() {
+        let table = LookupTable {
+            index_1: vec![0.5, 1.0],
+            index_2: vec![10.0, 20.0, 30.0],
+            values: vec![vec![0.1, 0.2, 0.3], vec![0.11, 0.21, 0.31]],
+        };
+        let group = table.to_group("cell_rise");
+        assert_eq!(group.type_, "cell_rise");
+        let mut lib = Group::new("library", "foo");
+        lib.groups.push(group);
+        let text = LibertyAst::new(vec![lib.into_group_item()]).to_string();
+        let parsed = LibertyAst::from_string(&text).unwrap().into_liberty();
+        assert_eq!(parsed["foo"].groups[0].as_lookup_table(), Some(table));
+    }
+
+    #[test]
+    fn test_receiver_capacitance() {
+        let lib = crate::parse_lib(
+            r#"library(foo) {
+                cell(INV) {
+                    pin(A) {
+                        receiver_capacitance() {
+                            receiver_capacitance1_rise(rc_template) {
+                                index_1 ("0.1, 0.2");
+                                values ("0.01, 0.02");
+                            }
+                        }
+                        timing() {
+                            receiver_capacitance() {
+                                receiver_capacitance1_rise(rc_template) {
+                                    index_1 ("0.1, 0.2");
+                                    index_2 ("1.0");
+                                    values ("0.03", "0.04");
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let pin = &lib["foo"].cells["INV"].pins["A"];
+        assert_eq!(
+            pin.receiver_capacitance("receiver_capacitance1_rise"),
+            Some(LookupTable {
+                index_1: vec![0.1, 0.2],
+                index_2: vec![],
+                values: vec![vec![0.01, 0.02]],
+            })
+        );
+        assert_eq!(pin.receiver_capacitance("receiver_capacitance1_fall"), None);
+        let timing = pin.groups.iter().find(|g| g.type_ == "timing").unwrap();
+        assert_eq!(
+            timing
+                .receiver_capacitance("receiver_capacitance1_rise")
+                .map(|t| t.values),
+            Some(vec![vec![0.03], vec![0.04]])
+        );
+    }
+}
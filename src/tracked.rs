@@ -0,0 +1,167 @@
+//! Change tracking for editing a [`Library`] in place
+//!
+//! [`TrackedLibrary`] remembers a hash of every cell when it's created, so after any number of
+//! edits it can report which cells differ and need to be written again. Edits made and then
+//! undone don't count as changes. Unlike [`Cell::fingerprint`], the hash covers comments and
+//! the sign of zero, since both are written out. Hashes are computed from the library in place,
+//! so checking for changes doesn't copy it.
+
+use std::collections::HashMap;
+
+use crate::liberty::{Cell, Library};
+
+/// [`Library`] that reports which of its cells changed since it was loaded
+#[derive(Debug, Clone)]
+pub struct TrackedLibrary {
+    library: Library,
+    // hash of each cell, and of everything outside the cells, when last marked clean
+    cells: HashMap<String, u64>,
+    rest: u64,
+}
+
+impl TrackedLibrary {
+    /// Start tracking changes to `library`
+    pub fn new(library: Library) -> Self {
+        let mut tracked = TrackedLibrary {
+            library,
+            cells: HashMap::new(),
+            rest: 0,
+        };
+        tracked.mark_clean();
+        tracked
+    }
+
+    /// Get the library
+    pub fn library(&self) -> &Library {
+        &self.library
+    }
+
+    /// Get a mutable reference to the library, for edits of any kind
+    pub fn library_mut(&mut self) -> &mut Library {
+        &mut self.library
+    }
+
+    /// Get a mutable reference to a cell by name
+    pub fn cell_mut(&mut self, name: &str) -> Option<&mut Cell> {
+        self.library.cells.get_mut(name)
+    }
+
+    /// Names of the cells that were added or edited since the last [`mark_clean`], sorted
+    ///
+    /// [`mark_clean`]: TrackedLibrary::mark_clean
+    pub fn changed_cells(&self) -> Vec<&str> {
+        let mut changed: Vec<&str> = self
+            .library
+            .cells
+            .iter()
+            .filter(|(name, cell)| self.cells.get(*name) != Some(&cell.exact_fingerprint()))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        changed.sort_unstable();
+        changed
+    }
+
+    /// Names of the cells that were removed since the last [`mark_clean`], sorted
+    ///
+    /// [`mark_clean`]: TrackedLibrary::mark_clean
+    pub fn removed_cells(&self) -> Vec<&str> {
+        let mut removed: Vec<&str> = self
+            .cells
+            .keys()
+            .filter(|name| !self.library.cells.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        removed.sort_unstable();
+        removed
+    }
+
+    /// Whether anything outside the cells, such as a library attribute, changed
+    pub fn is_library_changed(&self) -> bool {
+        self.library.exact_fingerprint_without_cells() != self.rest
+    }
+
+    /// Treat the current contents as unchanged
+    pub fn mark_clean(&mut self) {
+        self.cells = self
+            .library
+            .cells
+            .iter()
+            .map(|(name, cell)| (name.clone(), cell.exact_fingerprint()))
+            .collect();
+        self.rest = self.library.exact_fingerprint_without_cells();
+    }
+
+    /// Stop tracking and return the library
+    pub fn into_inner(self) -> Library {
+        self.library
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::Value;
+
+    #[test]
+    fn test_tracked_library() {
+        let liberty = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        let mut tracked = TrackedLibrary::new(liberty.0[0].clone());
+        assert!(tracked.changed_cells().is_empty());
+        assert!(!tracked.is_library_changed());
+
+        let area = Value::Float(5.0);
+        let old = tracked
+            .cell_mut("AND2")
+            .unwrap()
+            .simple_attributes
+            .insert("area".to_string(), area)
+            .unwrap();
+        assert_eq!(tracked.changed_cells(), ["AND2"]);
+        assert!(!tracked.is_library_changed());
+
+        // Undoing an edit leaves the cell unchanged
+        tracked
+            .cell_mut("AND2")
+            .unwrap()
+            .simple_attributes
+            .insert("area".to_string(), old);
+        assert!(tracked.changed_cells().is_empty());
+
+        // Edits that only show in the output still count
+        let cell = tracked.cell_mut("AND2").unwrap();
+        cell.comments
+            .insert("area".to_string(), "/* checked */".to_string());
+        assert_eq!(tracked.changed_cells(), ["AND2"]);
+        let cell = tracked.cell_mut("AND2").unwrap();
+        cell.comments.remove("area");
+        let area = cell.simple_attributes["area"].clone();
+        cell.simple_attributes
+            .insert("area".to_string(), Value::Float(-0.0));
+        tracked.mark_clean();
+        let cell = tracked.cell_mut("AND2").unwrap();
+        cell.simple_attributes
+            .insert("area".to_string(), Value::Float(0.0));
+        assert_eq!(tracked.changed_cells(), ["AND2"]);
+        tracked
+            .cell_mut("AND2")
+            .unwrap()
+            .simple_attributes
+            .insert("area".to_string(), area);
+        tracked.mark_clean();
+
+        let lib = tracked.library_mut();
+        lib.cells.remove("NAND2");
+        lib.cells.insert("BUF".to_string(), Cell::new("BUF"));
+        lib.simple_attributes
+            .insert("revision".to_string(), Value::Float(2.0));
+        assert_eq!(tracked.changed_cells(), ["BUF"]);
+        assert_eq!(tracked.removed_cells(), ["NAND2"]);
+        assert!(tracked.is_library_changed());
+
+        tracked.mark_clean();
+        assert!(tracked.changed_cells().is_empty());
+        assert!(tracked.removed_cells().is_empty());
+        assert!(!tracked.is_library_changed());
+        assert_eq!(tracked.into_inner().cells.len(), liberty.0[0].cells.len());
+    }
+}
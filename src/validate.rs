@@ -0,0 +1,613 @@
+//! Checks that a [`Liberty`] structure can be written out as valid Liberty syntax
+//!
+//! Parsed files always pass, but structures built or edited programmatically may not. Unlike
+//! [`lint`](crate::lint), which flags suspicious attributes, every [`ValidationError`] is a
+//! problem in the output.
+//!
+//! [`Liberty::validate_delay_model`] checks that `timing` groups use the kind of data the
+//! library's `delay_model` expects, and [`Liberty::validate_physical_sanity`] separately checks
+//! that physical attributes such as `area` aren't negative, which usually points to a sign
+//! error in the tool that wrote them.
+
+use std::{collections::HashMap, error, fmt};
+
+use crate::{
+    ast::Value,
+    liberty::Liberty,
+    table,
+    view::{GroupView, ViewVisitor},
+    visit::GroupPath,
+};
+
+/// Group types that must have a name
+const NAMED_GROUPS: &[&str] = &["library", "cell", "pin", "bus", "bundle"];
+
+/// Physical attribute that must not be negative, checked by [`Liberty::validate_physical_sanity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SanityRule<'a> {
+    pub attribute: &'a str,
+    /// Whether zero is a sane value, such as the `area` of a filler cell
+    pub allow_zero: bool,
+}
+
+/// Rules used by [`Liberty::validate_physical_sanity`]
+pub const PHYSICAL_ATTRIBUTES: &[SanityRule<'static>] = &[
+    SanityRule {
+        attribute: "area",
+        allow_zero: true,
+    },
+    SanityRule {
+        attribute: "capacitance",
+        allow_zero: true,
+    },
+    SanityRule {
+        attribute: "fanout_load",
+        allow_zero: true,
+    },
+    SanityRule {
+        attribute: "max_capacitance",
+        allow_zero: false,
+    },
+    SanityRule {
+        attribute: "max_fanout",
+        allow_zero: false,
+    },
+    SanityRule {
+        attribute: "max_transition",
+        allow_zero: false,
+    },
+];
+
+/// Physical attribute with an out-of-range value, found by [`Liberty::validate_physical_sanity`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanityError {
+    /// Path to the group containing the attribute, such as `library(foo)/cell(AND2)`
+    pub path: String,
+    pub attribute: String,
+    pub value: f64,
+}
+
+impl fmt::Display for SanityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: attribute '{}' has out-of-range value {}",
+            self.path, self.attribute, self.value
+        )
+    }
+}
+
+impl error::Error for SanityError {}
+
+/// Attributes of a scalar-model `timing` group, as used by `generic_cmos`
+const SCALAR_TIMING_ATTRIBUTES: &[&str] = &[
+    "intrinsic_rise",
+    "intrinsic_fall",
+    "rise_resistance",
+    "fall_resistance",
+    "slope_rise",
+    "slope_fall",
+];
+
+/// `timing` group that doesn't match its library's `delay_model`, found by
+/// [`Liberty::validate_delay_model`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelayModelError {
+    /// Path to the `timing` group, such as `library(foo)/cell(AND2)/pin(Y)/timing()`
+    pub path: String,
+    pub delay_model: String,
+    /// Whether the group has lookup tables, rather than only scalar attributes
+    pub has_tables: bool,
+}
+
+impl fmt::Display for DelayModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let found = if self.has_tables {
+            "lookup tables"
+        } else {
+            "no lookup tables"
+        };
+        write!(
+            f,
+            "{}: timing group has {} under delay_model '{}'",
+            self.path, found, self.delay_model
+        )
+    }
+}
+
+impl error::Error for DelayModelError {}
+
+/// Problem found by [`Liberty::validate`]
+///
+/// `path` is the chain of enclosing groups joined with `/`, such as `library(foo)/cell(AND2)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// An attribute has an infinite or NaN float value, which has no Liberty syntax
+    NonFiniteFloat { path: String, name: String },
+    /// A group that must be named, such as a `library`, `cell`, or `pin`, has an empty name
+    EmptyName { path: String },
+    /// A `cell` group isn't directly inside a `library`
+    MisplacedCell { path: String },
+    /// A table's `values` don't match the lengths of its `index_1` and `index_2`
+    ///
+    /// `expected` and `found` are the number of rows and columns.
+    TableShape {
+        path: String,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NonFiniteFloat { path, name } => {
+                write!(f, "{}: attribute '{}' is not finite", path, name)
+            }
+            ValidationError::EmptyName { path } => write!(f, "{}: group has no name", path),
+            ValidationError::MisplacedCell { path } => {
+                write!(f, "{}: cell is not directly inside a library", path)
+            }
+            ValidationError::TableShape {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: expected {}x{} table values, found {}x{}",
+                path, expected.0, expected.1, found.0, found.1
+            ),
+        }
+    }
+}
+
+impl ValidationError {
+    /// Path to the group with the problem
+    pub fn path(&self) -> &str {
+        match self {
+            ValidationError::NonFiniteFloat { path, .. }
+            | ValidationError::EmptyName { path }
+            | ValidationError::MisplacedCell { path }
+            | ValidationError::TableShape { path, .. } => path,
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+impl Liberty {
+    /// Check that every library is well-formed and can be written out as valid Liberty syntax
+    ///
+    /// Besides non-finite floats, this checks that libraries, cells, and pins are named, that
+    /// cells are directly inside a library, and that table `values` match their indices. All
+    /// problems found are returned, sorted by path.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut validator = Validator::default();
+        for lib in &self.0 {
+            GroupView::Library(lib).visit(&mut validator);
+        }
+        validator.errors.sort_by(|a, b| a.path().cmp(b.path()));
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+}
+
+impl Liberty {
+    /// Check the [`PHYSICAL_ATTRIBUTES`] for negative, or where not allowed zero, values
+    pub fn validate_physical_sanity(&self) -> Vec<SanityError> {
+        self.validate_physical_sanity_with(PHYSICAL_ATTRIBUTES)
+    }
+
+    /// Check float attributes named by `rules` for negative, or where not allowed zero, values
+    ///
+    /// Errors are sorted by path.
+    pub fn validate_physical_sanity_with(&self, rules: &[SanityRule]) -> Vec<SanityError> {
+        let mut checker = SanityChecker {
+            rules,
+            path: GroupPath::default(),
+            errors: vec![],
+        };
+        for lib in &self.0 {
+            GroupView::Library(lib).visit(&mut checker);
+        }
+        checker.errors.sort_by(|a, b| a.path.cmp(&b.path));
+        checker.errors
+    }
+}
+
+impl Liberty {
+    /// Check that `timing` groups match the `delay_model` of their library
+    ///
+    /// Under `table_lookup`, a timing group with scalar attributes such as `intrinsic_rise`
+    /// must also have lookup tables. Under `cmos` and `generic_cmos`, timing groups must not
+    /// have lookup tables. Libraries with no or another `delay_model` aren't checked. Errors are
+    /// sorted by path.
+    pub fn validate_delay_model(&self) -> Vec<DelayModelError> {
+        let mut errors = vec![];
+        for lib in &self.0 {
+            let delay_model = match lib.delay_model() {
+                Some(model @ ("table_lookup" | "cmos" | "generic_cmos")) => model,
+                _ => continue,
+            };
+            let mut checker = DelayModelChecker {
+                delay_model,
+                path: GroupPath::default(),
+                errors: vec![],
+            };
+            GroupView::Library(lib).visit(&mut checker);
+            errors.append(&mut checker.errors);
+        }
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        errors
+    }
+}
+
+struct DelayModelChecker<'a> {
+    delay_model: &'a str,
+    path: GroupPath,
+    errors: Vec<DelayModelError>,
+}
+
+impl<'a> ViewVisitor<'a> for DelayModelChecker<'_> {
+    fn enter_group(&mut self, group: &GroupView<'a>) {
+        let path = self.path.enter(group).to_string();
+        if group.type_() == "timing" {
+            let has_tables = group
+                .children()
+                .iter()
+                .any(|g| g.attributes().1.contains_key("values"));
+            let has_scalars = SCALAR_TIMING_ATTRIBUTES
+                .iter()
+                .any(|name| group.attributes().0.contains_key(*name));
+            let mismatch = if self.delay_model == "table_lookup" {
+                has_scalars && !has_tables
+            } else {
+                has_tables
+            };
+            if mismatch {
+                self.errors.push(DelayModelError {
+                    path,
+                    delay_model: self.delay_model.to_string(),
+                    has_tables,
+                });
+            }
+        }
+    }
+
+    fn leave_group(&mut self, _group: &GroupView<'a>) {
+        self.path.leave();
+    }
+}
+
+struct SanityChecker<'a> {
+    rules: &'a [SanityRule<'a>],
+    path: GroupPath,
+    errors: Vec<SanityError>,
+}
+
+impl ViewVisitor<'_> for SanityChecker<'_> {
+    fn enter_group(&mut self, group: &GroupView<'_>) {
+        self.path.enter(group);
+    }
+
+    fn leave_group(&mut self, _group: &GroupView<'_>) {
+        self.path.leave();
+    }
+
+    fn simple_attr(&mut self, name: &str, value: &Value) {
+        let (rule, value) = match (self.rules.iter().find(|r| r.attribute == name), value) {
+            (Some(rule), Value::Float(value)) => (rule, *value),
+            _ => return,
+        };
+        if value < 0.0 || (value == 0.0 && !rule.allow_zero) {
+            self.errors.push(SanityError {
+                path: self.path.path().to_string(),
+                attribute: name.to_string(),
+                value,
+            });
+        }
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    path: GroupPath,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn path(&self) -> String {
+        self.path.path().to_string()
+    }
+
+    fn check_table(&mut self, attributes: &HashMap<String, Vec<Value>>) {
+        let (index_1, index_2, index_3, rows) = match (
+            table::index(attributes, "index_1"),
+            table::index(attributes, "index_2"),
+            table::index(attributes, "index_3"),
+            table::rows(attributes),
+        ) {
+            (Some(i1), Some(i2), Some(i3), Some(rows)) if !i1.is_empty() => (i1, i2, i3, rows),
+            _ => return,
+        };
+        // Rows of `values` span every index but the last
+        let expected = match (index_2.len(), index_3.len()) {
+            (0, _) => (1, index_1.len()),
+            (len_2, 0) => (index_1.len(), len_2),
+            (len_2, len_3) => (index_1.len() * len_2, len_3),
+        };
+        let columns = rows.iter().map(Vec::len).collect::<Vec<_>>();
+        if columns.len() != expected.0 || columns.iter().any(|&len| len != expected.1) {
+            let found = (
+                columns.len(),
+                columns
+                    .iter()
+                    .copied()
+                    .find(|&len| len != expected.1)
+                    .unwrap_or(expected.1),
+            );
+            self.errors.push(ValidationError::TableShape {
+                path: self.path(),
+                expected,
+                found,
+            });
+        }
+    }
+
+    fn check_finite<'a>(&mut self, name: &str, mut values: impl Iterator<Item = &'a Value>) {
+        if !values.all(Value::is_finite) {
+            self.errors.push(ValidationError::NonFiniteFloat {
+                path: self.path(),
+                name: name.to_string(),
+            });
+        }
+    }
+}
+
+impl ViewVisitor<'_> for Validator {
+    fn enter_group(&mut self, group: &GroupView<'_>) {
+        let (type_, name) = group.header();
+        let misplaced = type_ == "cell" && self.path.group_type() != Some("library");
+        let path = self.path.enter(group).to_string();
+        if misplaced {
+            self.errors
+                .push(ValidationError::MisplacedCell { path: path.clone() });
+        }
+        if name.is_empty() && NAMED_GROUPS.contains(&type_) {
+            self.errors.push(ValidationError::EmptyName { path });
+        }
+        self.check_table(group.attributes().1);
+    }
+
+    fn leave_group(&mut self, _group: &GroupView<'_>) {
+        self.path.leave();
+    }
+
+    fn simple_attr(&mut self, name: &str, value: &Value) {
+        self.check_finite(name, std::iter::once(value));
+    }
+
+    fn complex_attr(&mut self, name: &str, values: &[Value]) {
+        self.check_finite(name, values.iter());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::liberty::Group;
+
+    #[test]
+    fn test_validate_non_finite() {
+        let mut liberty = crate::parse_lib(
+            "library(foo) {
+                cell(AND2) { area : 1; }
+            }",
+        )
+        .unwrap();
+        assert_eq!(liberty.validate(), Ok(()));
+        for contents in [
+            include_str!("../data/cells.lib"),
+            include_str!("../data/cells_timing.lib"),
+        ] {
+            assert_eq!(crate::parse_lib(contents).unwrap().validate(), Ok(()));
+        }
+
+        let cell = liberty
+            .get_library_mut("foo")
+            .unwrap()
+            .cells
+            .get_mut("AND2")
+            .unwrap();
+        cell.simple_attributes
+            .insert("area".to_string(), Value::Float(f64::NAN));
+        let mut group = Group::new("leakage_power", "");
+        group.complex_attributes.insert(
+            "values".to_string(),
+            vec![Value::FloatGroup(vec![1.0, f64::INFINITY])],
+        );
+        cell.groups.push(group);
+        assert_eq!(
+            liberty.validate(),
+            Err(vec![
+                ValidationError::NonFiniteFloat {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    name: "area".to_string(),
+                },
+                ValidationError::NonFiniteFloat {
+                    path: "library(foo)/cell(AND2)/leakage_power()".to_string(),
+                    name: "values".to_string(),
+                },
+            ])
+        );
+        let mut bytes = vec![];
+        let err = liberty.write_to(&mut bytes).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_validate_structure() {
+        let mut liberty = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) {
+                    leakage_power() {
+                        index_1 ("1, 2");
+                        values ("1, 2");
+                    }
+                    pin(o) {
+                        timing() {
+                            cell_rise(delay_template) {
+                                index_1 ("1, 2");
+                                index_2 ("1, 2, 3");
+                                values ("1, 2, 3", "4, 5");
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let lib = liberty.get_library_mut("foo").unwrap();
+        lib.cells
+            .insert("".to_string(), crate::liberty::Cell::new(""));
+        lib.groups.push(Group::new("cell", "OR2"));
+        lib.groups[0].groups.push(Group::new("cell", "INV"));
+        assert_eq!(
+            liberty.validate(),
+            Err(vec![
+                ValidationError::EmptyName {
+                    path: "library(foo)/cell()".to_string(),
+                },
+                ValidationError::TableShape {
+                    path: "library(foo)/cell(AND2)/pin(o)/timing()/cell_rise(delay_template)"
+                        .to_string(),
+                    expected: (2, 3),
+                    found: (2, 2),
+                },
+                ValidationError::MisplacedCell {
+                    path: "library(foo)/cell(OR2)/cell(INV)".to_string(),
+                },
+            ])
+        );
+
+        let mut table = Group::new("cell_rise", "delay_template_2x2x2");
+        for name in &["index_1", "index_2", "index_3"] {
+            table
+                .complex_attributes
+                .insert(name.to_string(), vec![Value::FloatGroup(vec![1.0, 2.0])]);
+        }
+        table.complex_attributes.insert(
+            "values".to_string(),
+            vec![Value::FloatGroup(vec![1.0, 2.0]); 4],
+        );
+        let mut validator = Validator::default();
+        GroupView::Group(&table).visit(&mut validator);
+        assert_eq!(validator.errors, vec![]);
+        table.complex_attributes.get_mut("values").unwrap().pop();
+        GroupView::Group(&table).visit(&mut validator);
+        assert_eq!(
+            validator.errors,
+            vec![ValidationError::TableShape {
+                path: "cell_rise(delay_template_2x2x2)".to_string(),
+                expected: (4, 2),
+                found: (3, 2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_physical_sanity() {
+        let liberty = crate::parse_lib(
+            "library(foo) {
+                cell(AND2) {
+                    area : -1;
+                    pin(o) { capacitance : 0; max_capacitance : 0; }
+                }
+                cell(FILL) { area : 0; }
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            liberty.validate_physical_sanity(),
+            vec![
+                SanityError {
+                    path: "library(foo)/cell(AND2)".to_string(),
+                    attribute: "area".to_string(),
+                    value: -1.0,
+                },
+                SanityError {
+                    path: "library(foo)/cell(AND2)/pin(o)".to_string(),
+                    attribute: "max_capacitance".to_string(),
+                    value: 0.0,
+                },
+            ]
+        );
+        let rules = [SanityRule {
+            attribute: "area",
+            allow_zero: false,
+        }];
+        assert_eq!(liberty.validate_physical_sanity_with(&rules).len(), 2);
+        let clean = crate::parse_lib(include_str!("../data/cells.lib")).unwrap();
+        assert_eq!(clean.validate_physical_sanity(), vec![]);
+    }
+
+    #[test]
+    fn test_validate_delay_model() {
+        let liberty = crate::parse_lib(
+            r#"library(foo) {
+                delay_model : generic_cmos;
+                cell(AND2) {
+                    pin(Y) {
+                        timing() {
+                            related_pin : "A";
+                            cell_rise(scalar) { values ("0.1"); }
+                        }
+                        timing() { related_pin : "B"; intrinsic_rise : 0.1; }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(liberty.delay_model(), Some("generic_cmos"));
+        assert_eq!(
+            liberty.validate_delay_model(),
+            vec![DelayModelError {
+                path: "library(foo)/cell(AND2)/pin(Y)/timing()".to_string(),
+                delay_model: "generic_cmos".to_string(),
+                has_tables: true,
+            }]
+        );
+
+        let mut table_lookup = liberty.clone();
+        table_lookup.0[0].simple_attributes.insert(
+            "delay_model".to_string(),
+            Value::Expression("table_lookup".to_string()),
+        );
+        assert_eq!(table_lookup.validate_delay_model().len(), 1);
+        assert!(!table_lookup.validate_delay_model()[0].has_tables);
+
+        let timing = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        assert_eq!(timing.validate_delay_model(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_non_finite() {
+        assert!(crate::parse_lib("library(foo) { area : 1e999; }").is_err());
+        assert!(crate::parse_lib(r#"library(foo) { values ("1, 1e999"); }"#).is_err());
+        let config = crate::ParseConfig {
+            non_finite_floats: true,
+            ..crate::ParseConfig::default()
+        };
+        let liberty = crate::parse_lib_with_config("library(foo) { area : 1e999; }", &config);
+        assert!(liberty.unwrap().validate().is_err());
+        let liberty = crate::parse_lib("library(foo) { area : nan; }").unwrap();
+        assert_eq!(
+            liberty["foo"].simple_attributes["area"],
+            Value::Expression("nan".to_string())
+        );
+    }
+}
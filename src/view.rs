@@ -0,0 +1,139 @@
+//! Borrowed view of any group of a [`Liberty`] structure
+//!
+//! [`Library`], [`Cell`], and [`Pin`] keep cells and pins apart from their other sub-groups, so
+//! code that treats every group alike would otherwise convert each library into a [`Group`]
+//! first, cloning all of it. [`GroupView`] reads the same data in place, and
+//! [`GroupView::visit`] walks it like [`Group::visit`].
+//!
+//! [`Liberty`]: crate::liberty::Liberty
+
+use std::collections::HashMap;
+
+use crate::ast::Value;
+use crate::liberty::{AttributeOrder, Cell, Group, Library, Pin};
+
+// Borrowed group of any kind, with sub-groups in the same order as `ToGroup::into_group`
+pub(crate) enum GroupView<'a> {
+    Library(&'a Library),
+    Cell(&'a Cell),
+    Pin(&'a Pin),
+    Group(&'a Group),
+}
+
+pub(crate) type Attributes<'a> = (
+    &'a HashMap<String, Value>,
+    &'a HashMap<String, Vec<Value>>,
+    &'a HashMap<String, String>,
+);
+
+// Callbacks for `GroupView::visit`, called in the same order as those of `GroupVisitor`
+#[allow(unused_variables)]
+pub(crate) trait ViewVisitor<'a> {
+    fn enter_group(&mut self, group: &GroupView<'a>) {}
+    fn leave_group(&mut self, group: &GroupView<'a>) {}
+    fn simple_attr(&mut self, name: &'a str, value: &'a Value) {}
+    fn complex_attr(&mut self, name: &'a str, values: &'a [Value]) {}
+}
+
+impl<'a> GroupView<'a> {
+    pub(crate) fn header(&self) -> (&'a str, &'a str) {
+        match self {
+            GroupView::Library(lib) => ("library", &lib.name),
+            GroupView::Cell(cell) => ("cell", &cell.name),
+            GroupView::Pin(pin) => ("pin", &pin.name),
+            GroupView::Group(group) => (&group.type_, &group.name),
+        }
+    }
+
+    pub(crate) fn type_(&self) -> &'a str {
+        self.header().0
+    }
+
+    pub(crate) fn attributes(&self) -> Attributes<'a> {
+        match self {
+            GroupView::Library(lib) => (
+                &lib.simple_attributes,
+                &lib.complex_attributes,
+                &lib.comments,
+            ),
+            GroupView::Cell(cell) => (
+                &cell.simple_attributes,
+                &cell.complex_attributes,
+                &cell.comments,
+            ),
+            GroupView::Pin(pin) => (
+                &pin.simple_attributes,
+                &pin.complex_attributes,
+                &pin.comments,
+            ),
+            GroupView::Group(group) => (
+                &group.simple_attributes,
+                &group.complex_attributes,
+                &group.comments,
+            ),
+        }
+    }
+
+    pub(crate) fn attribute_order(&self) -> &'a AttributeOrder {
+        match self {
+            GroupView::Library(lib) => &lib.attribute_order,
+            GroupView::Cell(cell) => &cell.attribute_order,
+            GroupView::Pin(pin) => &pin.attribute_order,
+            GroupView::Group(group) => &group.attribute_order,
+        }
+    }
+
+    pub(crate) fn unknown(&self) -> &'a [String] {
+        match self {
+            GroupView::Library(lib) => &lib.unknown,
+            GroupView::Cell(cell) => &cell.unknown,
+            GroupView::Pin(pin) => &pin.unknown,
+            GroupView::Group(group) => &group.unknown,
+        }
+    }
+
+    pub(crate) fn children(&self) -> Vec<GroupView<'a>> {
+        match self {
+            GroupView::Library(lib) => {
+                let mut cells: Vec<&Cell> = lib.cells.values().collect();
+                cells.sort_by(|a, b| a.name.cmp(&b.name));
+                cells
+                    .into_iter()
+                    .map(GroupView::Cell)
+                    .chain(lib.groups.iter().map(GroupView::Group))
+                    .collect()
+            }
+            GroupView::Cell(cell) => {
+                let mut pins: Vec<&Pin> = cell.pins.values().collect();
+                pins.sort_by(|a, b| a.name.cmp(&b.name));
+                pins.into_iter()
+                    .map(GroupView::Pin)
+                    .chain(cell.groups.iter().map(GroupView::Group))
+                    .collect()
+            }
+            GroupView::Pin(pin) => pin.groups.iter().map(GroupView::Group).collect(),
+            GroupView::Group(group) => group.groups.iter().map(GroupView::Group).collect(),
+        }
+    }
+
+    // Walk the group and its sub-groups depth-first, visiting attributes sorted by name, simple
+    // attributes first, as `Group::visit` does
+    pub(crate) fn visit<V: ViewVisitor<'a>>(&self, visitor: &mut V) {
+        visitor.enter_group(self);
+        let (simple, complex, _) = self.attributes();
+        let mut simple: Vec<_> = simple.iter().collect();
+        simple.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in simple {
+            visitor.simple_attr(name, value);
+        }
+        let mut complex: Vec<_> = complex.iter().collect();
+        complex.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, values) in complex {
+            visitor.complex_attr(name, values);
+        }
+        for child in self.children() {
+            child.visit(visitor);
+        }
+        visitor.leave_group(self);
+    }
+}
@@ -0,0 +1,233 @@
+//! Visitor API for traversing a [`Group`] tree
+//!
+//! [`Group::visit`] walks a group and all of its nested subgroups depth-first, calling the
+//! matching [`GroupVisitor`] method for each group and attribute. For each group,
+//! `enter_group` is called first, then `simple_attr` and `complex_attr` for its attributes,
+//! then its subgroups are visited in document order, and finally `leave_group` is called.
+//!
+//! Attributes are visited sorted by name, simple attributes first, rather than in
+//! [`Group::attribute_order`], so traversals don't depend on how a group was built.
+//!
+//! ```
+//! use liberty_parse::{liberty::Group, visit::GroupVisitor};
+//!
+//! struct TimingCollector(Vec<String>);
+//!
+//! impl GroupVisitor for TimingCollector {
+//!     fn enter_group(&mut self, group: &Group, _depth: usize) {
+//!         if group.type_ == "timing" {
+//!             self.0.push(group.name.clone());
+//!         }
+//!     }
+//! }
+//!
+//! let mut pin = Group::new("pin", "o");
+//! pin.groups.push(Group::new("timing", "t1"));
+//! pin.groups[0].groups.push(Group::new("timing", "t2"));
+//!
+//! let mut collector = TimingCollector(vec![]);
+//! pin.visit(&mut collector);
+//! assert_eq!(collector.0, vec!["t1", "t2"]);
+//! ```
+
+use crate::{ast::Value, liberty::Group, view::GroupView};
+
+/// Callbacks for [`Group::visit`]
+///
+/// `depth` is 0 for the group `visit` was called on and increases by one for each level of
+/// nesting. Attributes get the depth of the group they belong to. All methods default to doing
+/// nothing.
+#[allow(unused_variables)]
+pub trait GroupVisitor {
+    /// Called before a group's attributes and subgroups are visited
+    fn enter_group(&mut self, group: &Group, depth: usize) {}
+    /// Called after a group's attributes and subgroups are visited
+    fn leave_group(&mut self, group: &Group, depth: usize) {}
+    /// Called for each simple attribute
+    fn simple_attr(&mut self, name: &str, value: &Value, depth: usize) {}
+    /// Called for each complex attribute
+    fn complex_attr(&mut self, name: &str, values: &[Value], depth: usize) {}
+}
+
+/// Callbacks for [`Group::visit_mut`]
+///
+/// This is the mutable counterpart of [`GroupVisitor`]. Subgroups added in `enter_group` are
+/// visited too.
+#[allow(unused_variables)]
+pub trait GroupVisitorMut {
+    /// Called before a group's attributes and subgroups are visited
+    fn enter_group(&mut self, group: &mut Group, depth: usize) {}
+    /// Called after a group's attributes and subgroups are visited
+    fn leave_group(&mut self, group: &mut Group, depth: usize) {}
+    /// Called for each simple attribute
+    fn simple_attr(&mut self, name: &str, value: &mut Value, depth: usize) {}
+    /// Called for each complex attribute
+    fn complex_attr(&mut self, name: &str, values: &mut Vec<Value>, depth: usize) {}
+}
+
+// Type and `type(name)/...` path of each group enclosing the current point of a visit, for
+// visitors that report where they found something
+#[derive(Debug, Default)]
+pub(crate) struct GroupPath(Vec<(String, String)>);
+
+impl GroupPath {
+    // Call from `enter_group`, returning the group's path
+    pub(crate) fn enter(&mut self, group: &GroupView<'_>) -> &str {
+        let (type_, name) = group.header();
+        let segment = format!("{}({})", type_, name);
+        let path = match self.0.last() {
+            Some((_, parent)) => format!("{}/{}", parent, segment),
+            None => segment,
+        };
+        self.0.push((type_.to_string(), path));
+        self.path()
+    }
+
+    // Call from `leave_group`
+    pub(crate) fn leave(&mut self) {
+        self.0.pop();
+    }
+
+    // Path of the innermost group, or an empty string outside any group
+    pub(crate) fn path(&self) -> &str {
+        self.0.last().map_or("", |(_, path)| path)
+    }
+
+    // Type of the innermost group
+    pub(crate) fn group_type(&self) -> Option<&str> {
+        self.0.last().map(|(type_, _)| type_.as_str())
+    }
+}
+
+impl Group {
+    /// Walk this group and its subgroups with a [`GroupVisitor`]
+    pub fn visit<V: GroupVisitor>(&self, visitor: &mut V) {
+        self.visit_at(visitor, 0);
+    }
+
+    fn visit_at<V: GroupVisitor>(&self, visitor: &mut V, depth: usize) {
+        visitor.enter_group(self, depth);
+        let mut simple: Vec<_> = self.simple_attributes.iter().collect();
+        simple.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in simple {
+            visitor.simple_attr(name, value, depth);
+        }
+        let mut complex: Vec<_> = self.complex_attributes.iter().collect();
+        complex.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, values) in complex {
+            visitor.complex_attr(name, values, depth);
+        }
+        for group in &self.groups {
+            group.visit_at(visitor, depth + 1);
+        }
+        visitor.leave_group(self, depth);
+    }
+
+    /// Walk this group and its subgroups with a [`GroupVisitorMut`]
+    pub fn visit_mut<V: GroupVisitorMut>(&mut self, visitor: &mut V) {
+        self.visit_mut_at(visitor, 0);
+    }
+
+    fn visit_mut_at<V: GroupVisitorMut>(&mut self, visitor: &mut V, depth: usize) {
+        visitor.enter_group(self, depth);
+        let mut simple: Vec<_> = self.simple_attributes.iter_mut().collect();
+        simple.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in simple {
+            visitor.simple_attr(name, value, depth);
+        }
+        let mut complex: Vec<_> = self.complex_attributes.iter_mut().collect();
+        complex.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, values) in complex {
+            visitor.complex_attr(name, values, depth);
+        }
+        for group in &mut self.groups {
+            group.visit_mut_at(visitor, depth + 1);
+        }
+        visitor.leave_group(self, depth);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::liberty::ToGroup;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl GroupVisitor for Recorder {
+        fn enter_group(&mut self, group: &Group, depth: usize) {
+            self.0.push(format!("enter {} {}", group.type_, depth));
+        }
+        fn leave_group(&mut self, group: &Group, depth: usize) {
+            self.0.push(format!("leave {} {}", group.type_, depth));
+        }
+        fn simple_attr(&mut self, name: &str, _value: &Value, depth: usize) {
+            self.0.push(format!("simple {} {}", name, depth));
+        }
+        fn complex_attr(&mut self, name: &str, _values: &[Value], depth: usize) {
+            self.0.push(format!("complex {} {}", name, depth));
+        }
+    }
+
+    struct Scale(f64);
+
+    impl GroupVisitorMut for Scale {
+        fn simple_attr(&mut self, name: &str, value: &mut Value, _depth: usize) {
+            if let ("capacitance", Value::Float(v)) = (name, value) {
+                *v *= self.0;
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_order() {
+        let lib = crate::parse_lib(
+            r#"library(foo) {
+                cell(AND2) {
+                    area : 1;
+                    pin(o) {
+                        direction : output;
+                        capacitance : 0.5;
+                        timing() { related_pin : "a"; }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let mut recorder = Recorder::default();
+        lib["foo"].cells["AND2"]
+            .clone()
+            .into_group()
+            .visit(&mut recorder);
+        assert_eq!(
+            recorder.0,
+            vec![
+                "enter cell 0",
+                "simple area 0",
+                "enter pin 1",
+                "simple capacitance 1",
+                "simple direction 1",
+                "enter timing 2",
+                "simple related_pin 2",
+                "leave timing 2",
+                "leave pin 1",
+                "leave cell 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        let mut pin = Group::new("pin", "a");
+        pin.simple_attributes
+            .insert("capacitance".to_string(), Value::Float(0.5));
+        let mut cell = Group::new("cell", "INV");
+        cell.groups.push(pin);
+        cell.visit_mut(&mut Scale(2.0));
+        assert_eq!(
+            cell.groups[0].simple_attribute("capacitance"),
+            Some(&Value::Float(1.0))
+        );
+    }
+}
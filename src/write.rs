@@ -0,0 +1,211 @@
+//! Streaming output of a [`Liberty`] structure
+//!
+//! The output matches the `Display` implementation of the equivalent [`LibertyAst`], but is
+//! written group by group from borrowed data instead of building an AST or a `String` first.
+//!
+//! [`LibertyAst`]: crate::ast::LibertyAst
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::ast::{
+    write_complex_attr, write_group_close, write_group_open, write_simple_attr, ParenStyle,
+};
+use crate::liberty::{Attribute, Liberty};
+use crate::view::GroupView;
+use crate::Value;
+
+impl GroupView<'_> {
+    fn write<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let (type_, name) = self.header();
+        let (simple, complex, comments) = self.attributes();
+        write_group_open(w, type_, name, ParenStyle::default())?;
+        let mut first = true;
+        let mut separate = |w: &mut W| {
+            if first {
+                first = false;
+                Ok(())
+            } else {
+                writeln!(w)
+            }
+        };
+        let mut attributes: Vec<_> = simple
+            .iter()
+            .map(|(name, value)| (name, Attribute::Simple(value)))
+            .chain(
+                complex
+                    .iter()
+                    .map(|(name, values)| (name, Attribute::Complex(values))),
+            )
+            .collect();
+        self.attribute_order()
+            .sort(&mut attributes, |(name, _)| name.as_str());
+        for (name, attribute) in attributes {
+            separate(w)?;
+            let comment = comments.get(name).map(String::as_str);
+            match attribute {
+                Attribute::Simple(value) => write_simple_attr(w, name, value, 0, comment)?,
+                Attribute::Complex(values) => {
+                    write_complex_attr(w, name, values, comment, ParenStyle::default())?
+                }
+            }
+        }
+        for statement in self.unknown() {
+            separate(w)?;
+            writeln!(w, "{}", statement)?;
+        }
+        for child in self.children() {
+            separate(w)?;
+            child.write(w)?;
+        }
+        write_group_close(w)
+    }
+}
+
+fn write_libraries<W: fmt::Write>(w: &mut W, liberty: &Liberty) -> fmt::Result {
+    for (idx, lib) in liberty.0.iter().enumerate() {
+        if idx > 0 {
+            writeln!(w)?;
+        }
+        GroupView::Library(lib).write(w)?;
+    }
+    Ok(())
+}
+
+// Forwards formatted output to an `io::Write`, keeping the first I/O error
+struct IoAdapter<'a, W: Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: Write> fmt::Write for IoAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl Liberty {
+    /// Write the Liberty file representation to `w`
+    ///
+    /// Output is streamed group by group, so no intermediate `String` or AST is built. Fails with
+    /// [`io::ErrorKind::InvalidData`] before writing anything if a float isn't finite, rather
+    /// than writing the sentinel used by `Display`.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for lib in &self.0 {
+            check_finite(&GroupView::Library(lib))?;
+        }
+        write_io(w, |adapter| write_libraries(adapter, self))
+    }
+
+    /// Write each library to its own file, at the path returned by `name_to_path` for the
+    /// library's name
+    ///
+    /// Files are created or truncated. Stops at the first error, leaving earlier files written.
+    pub fn write_split<F: FnMut(&str) -> PathBuf>(&self, mut name_to_path: F) -> io::Result<()> {
+        self.write_split_with(|name| File::create(name_to_path(name)).map(BufWriter::new))
+    }
+
+    /// Write each library to its own sink, as returned by `open` for the library's name
+    ///
+    /// Each sink is flushed after its library is written. A library with a non-finite float
+    /// fails with [`io::ErrorKind::InvalidData`] before its sink is opened.
+    pub fn write_split_with<W, F>(&self, mut open: F) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&str) -> io::Result<W>,
+    {
+        for lib in &self.0 {
+            check_finite(&GroupView::Library(lib))?;
+            let mut w = open(&lib.name)?;
+            write_io(&mut w, |adapter| GroupView::Library(lib).write(adapter))?;
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn write_io<W: Write>(
+    w: &mut W,
+    f: impl FnOnce(&mut IoAdapter<'_, W>) -> fmt::Result,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter {
+        inner: w,
+        error: None,
+    };
+    f(&mut adapter).map_err(|_| {
+        adapter
+            .error
+            .take()
+            .unwrap_or_else(|| io::Error::other("formatter error"))
+    })
+}
+
+// Fail if any float in `node` or its descendants is infinite or NaN
+fn check_finite(node: &GroupView<'_>) -> io::Result<()> {
+    let (simple, complex, _) = node.attributes();
+    let finite =
+        simple.values().all(Value::is_finite) && complex.values().flatten().all(Value::is_finite);
+    if !finite {
+        let (type_, name) = node.header();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("non-finite float in {}({})", type_, name),
+        ));
+    }
+    node.children().iter().try_for_each(check_finite)
+}
+
+impl fmt::Display for Liberty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_libraries(f, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ast::LibertyAst;
+
+    #[test]
+    fn test_write_to() {
+        let liberty = crate::parse_lib(include_str!("../data/cells_timing.lib")).unwrap();
+        let mut bytes = vec![];
+        liberty.write_to(&mut bytes).unwrap();
+        let streamed = String::from_utf8(bytes).unwrap();
+        assert_eq!(streamed, liberty.to_string());
+        assert_eq!(
+            streamed,
+            LibertyAst::from_liberty(liberty.clone()).to_string()
+        );
+        assert_eq!(crate::parse_lib(&streamed).unwrap(), liberty);
+    }
+
+    #[test]
+    fn test_write_split() {
+        let liberty = crate::parse_lib(
+            "library(foo) { cell(AND2) { area : 1; } }
+            library(bar) { cell(INV) { area : 2; } }",
+        )
+        .unwrap();
+        let mut sinks = vec![vec![], vec![]];
+        let mut free = sinks.iter_mut();
+        let mut names = vec![];
+        liberty
+            .write_split_with(|name| {
+                names.push(name.to_string());
+                Ok(free.next().unwrap())
+            })
+            .unwrap();
+        assert_eq!(names, ["foo", "bar"]);
+        for (lib, bytes) in liberty.0.iter().zip(sinks) {
+            let split = crate::parse_lib(&String::from_utf8(bytes).unwrap()).unwrap();
+            assert_eq!(&split.0, std::slice::from_ref(lib));
+        }
+    }
+}